@@ -0,0 +1,35 @@
+use std::fmt;
+
+/// The reason an operation on a linestring failed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineStringError {
+	/// The linestring has fewer than two points
+	EmptyLine,
+	/// The linestring has points, but they are all in the same place
+	ZeroLength,
+	/// A coordinate, distance or position is NaN or infinite
+	NonFinite,
+	/// A position is outside the range `min..=max`
+	OutOfRange { value: f64, min: f64, max: f64 },
+	/// The segment at `index` has zero length, so it has no direction
+	DegenerateSegment { index: usize },
+	/// The number of measures does not match the number of points
+	MeasureCountMismatch { points: usize, measures: usize },
+}
+
+impl fmt::Display for LineStringError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LineStringError::EmptyLine => write!(f, "the linestring has fewer than two points"),
+			LineStringError::ZeroLength => write!(f, "the linestring has zero length"),
+			LineStringError::NonFinite => write!(f, "a value is NaN or infinite"),
+			LineStringError::OutOfRange { value, min, max } => write!(f, "{} is outside the range {} to {}", value, min, max),
+			LineStringError::DegenerateSegment { index } => write!(f, "segment {} has zero length", index),
+			LineStringError::MeasureCountMismatch { points, measures } => {
+				write!(f, "there are {} measures for {} points", measures, points)
+			}
+		}
+	}
+}
+
+impl std::error::Error for LineStringError {}
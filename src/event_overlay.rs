@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use crate::line_string_measured::LineStringMeasured;
+use crate::route_events::{Attributes, DynamicSegmentation, EventError, LinearEvent};
+
+/// A linear event and the part of its route which it covers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+	pub event: LinearEvent,
+	pub line: LineStringMeasured,
+}
+
+impl DynamicSegmentation {
+	/// Locates every event, as in `locate_linear`, stopping at the first which fails.
+	pub fn segment_table(&self, events: &[LinearEvent]) -> Result<Vec<Segment>, EventError> {
+		events
+			.iter()
+			.map(|event| self.locate_linear(event).map(|line| Segment { event: event.clone(), line }))
+			.collect()
+	}
+}
+
+/// Splits the tables at every event boundary and keeps the sections covered by at least one event in every table.
+/// See `overlay` for how the output is built.
+pub fn intersect(tables: &[&[LinearEvent]]) -> Vec<LinearEvent> {
+	overlay(tables, |covering_tables, table_count| covering_tables == table_count)
+}
+
+/// Splits the tables at every event boundary and keeps the sections covered by at least one event in any table.
+/// See `overlay` for how the output is built.
+pub fn union(tables: &[&[LinearEvent]]) -> Vec<LinearEvent> {
+	overlay(tables, |covering_tables, _| covering_tables > 0)
+}
+
+/// An event on one route, as (from, to, position in its table, event) with from <= to.
+type RouteEvent<'a> = (f64, f64, usize, &'a LinearEvent);
+
+/// Splits the tables at every event boundary on each route, and keeps the sections for which `keep` returns true
+/// when called with the number of tables which cover the section and the total number of tables.
+///
+/// Each output event runs forwards from the lower measure to the higher, has no offset, and has the attributes of every
+/// event covering it merged in table order; where two events have the same attribute the later one wins.
+/// Routes are output in the order they are first seen, and sections along each route in increasing measure.
+pub fn overlay<F: Fn(usize, usize) -> bool>(tables: &[&[LinearEvent]], keep: F) -> Vec<LinearEvent> {
+	// the events on each route in each table
+	let mut routes: Vec<&str> = Vec::new();
+	let mut route_indices: HashMap<&str, usize> = HashMap::new();
+	let mut events_by_route: Vec<Vec<Vec<RouteEvent>>> = Vec::new();
+	for (table_index, table) in tables.iter().enumerate() {
+		for (position, event) in table.iter().enumerate() {
+			let route_index = *route_indices.entry(&event.route).or_insert_with(|| {
+				routes.push(&event.route);
+				events_by_route.push(vec![Vec::new(); tables.len()]);
+				routes.len() - 1
+			});
+			let (from, to) = (event.from_measure.min(event.to_measure), event.from_measure.max(event.to_measure));
+			events_by_route[route_index][table_index].push((from, to, position, event));
+		}
+	}
+
+	let mut result = Vec::new();
+	for (route, mut route_tables) in routes.into_iter().zip(events_by_route) {
+		for events in route_tables.iter_mut() {
+			events.sort_by(|a, b| a.0.total_cmp(&b.0));
+		}
+
+		let mut breakpoints: Vec<f64> = route_tables.iter().flatten().flat_map(|&(from, to, _, _)| vec![from, to]).collect();
+		breakpoints.sort_by(|a, b| a.total_cmp(b));
+		breakpoints.dedup();
+
+		// sweep along the route, keeping the indices of the events in each table which cover the current section
+		let mut next_to_start = vec![0usize; route_tables.len()];
+		let mut active: Vec<Vec<usize>> = vec![Vec::new(); route_tables.len()];
+		for window in breakpoints.windows(2) {
+			let (from, to) = (window[0], window[1]);
+			for (table_index, events) in route_tables.iter().enumerate() {
+				while next_to_start[table_index] < events.len() && events[next_to_start[table_index]].0 <= from {
+					active[table_index].push(next_to_start[table_index]);
+					next_to_start[table_index] += 1;
+				}
+				active[table_index].retain(|&index| events[index].1 > from);
+			}
+			let covering_tables = active.iter().filter(|indices| !indices.is_empty()).count();
+			if !keep(covering_tables, tables.len()) {
+				continue;
+			}
+			let mut attributes = Attributes::new();
+			for (table_index, events) in route_tables.iter().enumerate() {
+				let mut indices = active[table_index].clone();
+				// merge in the order the events were given, not the order they start
+				indices.sort_by_key(|&index| events[index].2);
+				for index in indices {
+					attributes.extend(events[index].3.attributes.clone());
+				}
+			}
+			result.push(LinearEvent::new(route, from, to, attributes));
+		}
+	}
+	result
+}
+
+/// Merges each event into the one before it if they are on the same route, touch or overlap, and have the same offset and attributes.
+/// Events are first sorted by route and measure, and the results run forwards from the lower measure to the higher.
+pub fn dissolve(events: &[LinearEvent]) -> Vec<LinearEvent> {
+	let mut sorted: Vec<LinearEvent> = events
+		.iter()
+		.map(|event| LinearEvent {
+			from_measure: event.from_measure.min(event.to_measure),
+			to_measure: event.from_measure.max(event.to_measure),
+			..event.clone()
+		})
+		.collect();
+	sorted.sort_by(|a, b| a.route.cmp(&b.route).then(a.from_measure.total_cmp(&b.from_measure)));
+
+	let mut result: Vec<LinearEvent> = Vec::new();
+	for event in sorted {
+		match result.last_mut() {
+			Some(last)
+				if last.route == event.route
+					&& event.from_measure <= last.to_measure
+					&& last.offset == event.offset
+					&& last.attributes == event.attributes =>
+			{
+				last.to_measure = last.to_measure.max(event.to_measure);
+			}
+			_ => result.push(event),
+		}
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::line_string_m::LineStringM;
+	use crate::route_events::AttributeValue;
+	use serde_json::{json, Value};
+
+	fn event(route: &str, from_measure: f64, to_measure: f64, attributes: Value) -> LinearEvent {
+		LinearEvent::new(route, from_measure, to_measure, serde_json::from_value(attributes).unwrap())
+	}
+
+	fn pavement() -> Vec<LinearEvent> {
+		vec![
+			event("A", 0.0, 1000.0, json!({"pavement": "asphalt"})),
+			event("A", 2000.0, 1000.0, json!({"pavement": "concrete"})),
+		]
+	}
+
+	fn speed() -> Vec<LinearEvent> {
+		vec![
+			event("A", 1500.0, 2000.0, json!({"speed": 80})),
+			event("A", 500.0, 1500.0, json!({"speed": 60})),
+		]
+	}
+
+	#[test]
+	fn intersect_and_union() {
+		let (pavement, speed) = (pavement(), speed());
+		assert_eq!(
+			intersect(&[&pavement, &speed]),
+			vec![
+				event("A", 500.0, 1000.0, json!({"pavement": "asphalt", "speed": 60})),
+				event("A", 1000.0, 1500.0, json!({"pavement": "concrete", "speed": 60})),
+				event("A", 1500.0, 2000.0, json!({"pavement": "concrete", "speed": 80})),
+			]
+		);
+		assert_eq!(
+			union(&[&pavement, &speed])[0],
+			event("A", 0.0, 500.0, json!({"pavement": "asphalt"}))
+		);
+		assert_eq!(union(&[&pavement, &speed]).len(), 4);
+
+		let crashes = vec![event("A", 900.0, 1100.0, json!({"crashes": 3})), event("B", 0.0, 10.0, json!({"crashes": 1}))];
+		let sections = intersect(&[&pavement, &speed, &crashes]);
+		assert_eq!(
+			sections,
+			vec![
+				event("A", 900.0, 1000.0, json!({"pavement": "asphalt", "speed": 60, "crashes": 3})),
+				event("A", 1000.0, 1100.0, json!({"pavement": "concrete", "speed": 60, "crashes": 3})),
+			]
+		);
+		assert_eq!(union(&[&pavement, &crashes]).last(), Some(&event("B", 0.0, 10.0, json!({"crashes": 1}))));
+	}
+
+	#[test]
+	fn overlapping_events_in_one_table() {
+		let table = vec![event("A", 0.0, 10.0, json!({"a": 1, "b": 1})), event("A", 5.0, 15.0, json!({"b": 2}))];
+		assert_eq!(
+			union(&[&table]),
+			vec![
+				event("A", 0.0, 5.0, json!({"a": 1, "b": 1})),
+				event("A", 5.0, 10.0, json!({"a": 1, "b": 2})),
+				event("A", 10.0, 15.0, json!({"b": 2})),
+			]
+		);
+	}
+
+	#[test]
+	fn routes_in_order_first_seen() {
+		let first = vec![event("C", 0.0, 1.0, json!({"a": 1})), event("A", 0.0, 1.0, json!({"a": 2})), event("C", 1.0, 2.0, json!({"a": 3}))];
+		let second = vec![event("B", 0.0, 1.0, json!({"b": 1})), event("A", 1.0, 0.5, json!({"b": 2})), event("C", 0.5, 1.5, json!({"b": 3}))];
+		assert_eq!(
+			union(&[&first, &second]).iter().map(|event| (event.route.as_str(), event.from_measure)).collect::<Vec<(&str, f64)>>(),
+			vec![("C", 0.0), ("C", 0.5), ("C", 1.0), ("C", 1.5), ("A", 0.0), ("A", 0.5), ("B", 0.0)]
+		);
+		assert_eq!(
+			intersect(&[&first, &second]),
+			vec![
+				event("C", 0.5, 1.0, json!({"a": 1, "b": 3})),
+				event("C", 1.0, 1.5, json!({"a": 3, "b": 3})),
+				event("A", 0.5, 1.0, json!({"a": 2, "b": 2})),
+			]
+		);
+	}
+
+	#[test]
+	fn dissolve_contiguous() {
+		let (pavement, speed) = (pavement(), speed());
+		let pavement_only: Vec<LinearEvent> = intersect(&[&pavement, &speed])
+			.into_iter()
+			.map(|mut event| {
+				event.attributes.remove("speed");
+				event
+			})
+			.collect();
+		assert_eq!(
+			dissolve(&pavement_only),
+			vec![event("A", 500.0, 1000.0, json!({"pavement": "asphalt"})), event("A", 1000.0, 2000.0, json!({"pavement": "concrete"}))]
+		);
+		let gap = vec![event("A", 0.0, 1.0, json!({})), event("A", 2.0, 3.0, json!({})), event("A", 1.5, 0.5, json!({})), event("A", 3.0, 4.0, json!({"a": 1}))];
+		assert_eq!(
+			dissolve(&gap),
+			vec![event("A", 0.0, 1.5, json!({})), event("A", 2.0, 3.0, json!({})), event("A", 3.0, 4.0, json!({"a": 1}))]
+		);
+	}
+
+	#[test]
+	fn segment_table() {
+		let mut routes = HashMap::new();
+		routes.insert("A".to_string(), LineStringM::from(vec![(0.0, 0.0, 0.0), (20.0, 0.0, 2000.0)]));
+		let segmentation = DynamicSegmentation::new(routes);
+		let (pavement, speed) = (pavement(), speed());
+		let segments = segmentation.segment_table(&intersect(&[&pavement, &speed])).unwrap();
+		assert_eq!(segments.len(), 3);
+		assert_eq!(segments[1].line.into_tuples(), vec![(10.0, 0.0), (15.0, 0.0)]);
+		assert_eq!(segments[1].event.attributes["pavement"], AttributeValue::from("concrete"));
+		assert_eq!(
+			segmentation.segment_table(&[event("B", 0.0, 1.0, json!({}))]),
+			Err(EventError::UnknownRoute("B".to_string()))
+		);
+	}
+}
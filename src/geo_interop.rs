@@ -0,0 +1,95 @@
+use geo_types::{Coord, LineString, MultiLineString, Point};
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+
+// Conversions to and from `geo-types`, available with the `geo-types` feature.
+
+impl From<Vector2> for Coord<f64> {
+	fn from(other: Vector2) -> Self {
+		Coord { x: other.x, y: other.y }
+	}
+}
+
+impl From<Coord<f64>> for Vector2 {
+	fn from(other: Coord<f64>) -> Self {
+		Vector2::new(other.x, other.y)
+	}
+}
+
+impl From<Vector2> for Point<f64> {
+	fn from(other: Vector2) -> Self {
+		Point::new(other.x, other.y)
+	}
+}
+
+impl From<Point<f64>> for Vector2 {
+	fn from(other: Point<f64>) -> Self {
+		Vector2::new(other.x(), other.y())
+	}
+}
+
+impl From<&LineStringMeasured> for LineString<f64> {
+	fn from(other: &LineStringMeasured) -> Self {
+		let points: Vec<Vector2> = other.into();
+		points.into_iter().map(Coord::from).collect()
+	}
+}
+
+impl From<LineStringMeasured> for LineString<f64> {
+	fn from(other: LineStringMeasured) -> Self {
+		(&other).into()
+	}
+}
+
+impl From<&LineString<f64>> for LineStringMeasured {
+	fn from(other: &LineString<f64>) -> Self {
+		other.coords().map(|&coord| Vector2::from(coord)).collect::<Vec<Vector2>>().into()
+	}
+}
+
+impl From<LineString<f64>> for LineStringMeasured {
+	fn from(other: LineString<f64>) -> Self {
+		(&other).into()
+	}
+}
+
+pub fn to_multi_line_string(lines: &[LineStringMeasured]) -> MultiLineString<f64> {
+	MultiLineString(lines.iter().map(LineString::from).collect())
+}
+
+pub fn from_multi_line_string(multi_line_string: &MultiLineString<f64>) -> Vec<LineStringMeasured> {
+	multi_line_string.iter().map(LineStringMeasured::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn vector2() {
+		let v = Vector2::new(1.5, -2.0);
+		assert_eq!(Coord::from(v), Coord { x: 1.5, y: -2.0 });
+		assert_eq!(Vector2::from(Coord { x: 1.5, y: -2.0 }), v);
+		assert_eq!(Point::from(v), Point::new(1.5, -2.0));
+		assert_eq!(Vector2::from(Point::new(1.5, -2.0)), v);
+	}
+
+	#[test]
+	fn line_strings() {
+		let line_string: LineString<f64> = vec![(0.0, 0.0), (3.0, 0.0), (3.0, 4.0)].into();
+		let ls = LineStringMeasured::from(&line_string);
+		assert_eq!(ls.mag, 7.0);
+		assert_eq!(LineString::from(&ls), line_string);
+
+		// cut and offset, then go back to geo-types
+		let (_, second_half) = ls.cut(0.5);
+		let offset: LineString<f64> = second_half.unwrap().offset(1.0).unwrap().remove(0).into();
+		assert_eq!(offset, vec![(2.0, 0.5), (2.0, 4.0)].into());
+
+		let multi = MultiLineString(vec![line_string.clone(), vec![(5.0, 5.0), (6.0, 6.0)].into()]);
+		let lines = from_multi_line_string(&multi);
+		assert_eq!(lines.len(), 2);
+		assert_eq!(to_multi_line_string(&lines), multi);
+	}
+}
@@ -0,0 +1,275 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::line_string_m::LineStringM;
+use crate::polyline;
+
+/// A GeoJSON geometry. Positions are lists of numbers so that a third coordinate can carry measures.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+pub enum Geometry {
+	LineString(Vec<Vec<f64>>),
+	MultiLineString(Vec<Vec<Vec<f64>>>),
+	Polygon(Vec<Vec<Vec<f64>>>),
+	MultiPolygon(Vec<Vec<Vec<Vec<f64>>>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct Feature {
+	pub geometry: Option<Geometry>,
+	pub properties: Option<Map<String, Value>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection {
+	pub features: Vec<Feature>,
+}
+
+/// Where the measures of a `LineStringM` are stored in a GeoJSON feature.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Measures {
+	/// As the third number of each position
+	Coordinate,
+	/// As a list of numbers, one per position, in the named property
+	Property(String),
+}
+
+/// The reason a GeoJSON geometry or feature could not be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoJsonError {
+	/// The geometry type is not one which can be read into the requested type
+	UnsupportedGeometry(String),
+	/// The feature has no geometry
+	MissingGeometry,
+	/// A position has fewer numbers than needed
+	WrongDimension { expected: usize, found: usize },
+	/// The measures property is missing or is not a list of numbers
+	MissingMeasures,
+	/// The number of measures does not match the number of positions
+	MeasureCountMismatch { points: usize, measures: usize },
+}
+
+impl fmt::Display for GeoJsonError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			GeoJsonError::UnsupportedGeometry(name) => write!(f, "unsupported GeoJSON geometry type {}", name),
+			GeoJsonError::MissingGeometry => write!(f, "the GeoJSON feature has no geometry"),
+			GeoJsonError::WrongDimension { expected, found } => write!(f, "expected at least {} numbers per position but found {}", expected, found),
+			GeoJsonError::MissingMeasures => write!(f, "the GeoJSON feature has no measures"),
+			GeoJsonError::MeasureCountMismatch { points, measures } => write!(f, "there are {} measures for {} positions", measures, points),
+		}
+	}
+}
+
+impl std::error::Error for GeoJsonError {}
+
+impl Geometry {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Geometry::LineString(_) => "LineString",
+			Geometry::MultiLineString(_) => "MultiLineString",
+			Geometry::Polygon(_) => "Polygon",
+			Geometry::MultiPolygon(_) => "MultiPolygon",
+		}
+	}
+}
+
+impl Feature {
+	pub fn new(geometry: Geometry) -> Feature {
+		Feature {
+			geometry: Some(geometry),
+			properties: Some(Map::new()),
+		}
+	}
+}
+
+impl LineStringMeasured {
+	pub fn to_geojson(&self) -> Geometry {
+		let points: Vec<Vector2> = self.into();
+		Geometry::LineString(positions(&points))
+	}
+
+	/// Reads a `LineString` geometry. Any numbers after `x` and `y` are ignored.
+	pub fn from_geojson(geometry: &Geometry) -> Result<LineStringMeasured, GeoJsonError> {
+		match geometry {
+			Geometry::LineString(coordinates) => Ok(points(coordinates, 2)?.into()),
+			other => Err(GeoJsonError::UnsupportedGeometry(other.type_name().to_string())),
+		}
+	}
+}
+
+impl LineStringM {
+	pub fn to_feature(&self, measures: &Measures) -> Feature {
+		let tuples = self.into_tuples_measured();
+		match measures {
+			Measures::Coordinate => Feature::new(Geometry::LineString(tuples.iter().map(|&(x, y, m)| vec![x, y, m]).collect())),
+			Measures::Property(name) => {
+				let mut properties = Map::new();
+				properties.insert(name.clone(), Value::from(self.measures.clone()));
+				Feature {
+					geometry: Some(self.line.to_geojson()),
+					properties: Some(properties),
+				}
+			}
+		}
+	}
+
+	/// Reads a feature with a `LineString` geometry, taking the measures from where `measures` says they are.
+	pub fn from_feature(feature: &Feature, measures: &Measures) -> Result<LineStringM, GeoJsonError> {
+		let coordinates = match &feature.geometry {
+			Some(Geometry::LineString(coordinates)) => coordinates,
+			Some(other) => return Err(GeoJsonError::UnsupportedGeometry(other.type_name().to_string())),
+			None => return Err(GeoJsonError::MissingGeometry),
+		};
+		let measure_values: Vec<f64> = match measures {
+			Measures::Coordinate => {
+				points(coordinates, 3)?;
+				coordinates.iter().map(|position| position[2]).collect()
+			}
+			Measures::Property(name) => feature
+				.properties
+				.as_ref()
+				.and_then(|properties| properties.get(name))
+				.and_then(|value| value.as_array())
+				.and_then(|values| values.iter().map(|value| value.as_f64()).collect::<Option<Vec<f64>>>())
+				.ok_or(GeoJsonError::MissingMeasures)?,
+		};
+		if measure_values.len() != coordinates.len() {
+			return Err(GeoJsonError::MeasureCountMismatch { points: coordinates.len(), measures: measure_values.len() });
+		}
+		Ok(LineStringM::from_parts(points(coordinates, 2)?.into(), measure_values))
+	}
+}
+
+pub fn multi_line_string(lines: &[LineStringMeasured]) -> Geometry {
+	Geometry::MultiLineString(lines
+		.iter()
+		.map(|line| {
+			let points: Vec<Vector2> = line.into();
+			positions(&points)
+		})
+		.collect())
+}
+
+/// Reads a `LineString` or `MultiLineString` geometry into a list of linestrings.
+pub fn read_multi_line_string(geometry: &Geometry) -> Result<Vec<LineStringMeasured>, GeoJsonError> {
+	match geometry {
+		Geometry::LineString(_) => Ok(vec![LineStringMeasured::from_geojson(geometry)?]),
+		Geometry::MultiLineString(parts) => parts.iter().map(|part| Ok(points(part, 2)?.into())).collect(),
+		other => Err(GeoJsonError::UnsupportedGeometry(other.type_name().to_string())),
+	}
+}
+
+/// Converts polygons, such as those returned by `offset_ring`, into a `Polygon` if there is exactly one, or a `MultiPolygon`.
+pub fn polygons(polygons: &[Vec<Vec<Vector2>>]) -> Geometry {
+	let mut coordinates: Vec<Vec<Vec<Vec<f64>>>> = polygons
+		.iter()
+		.map(|rings| rings.iter().map(|ring| positions(ring)).collect())
+		.collect();
+	if coordinates.len() == 1 {
+		Geometry::Polygon(coordinates.remove(0))
+	} else {
+		Geometry::MultiPolygon(coordinates)
+	}
+}
+
+/// Converts the rings returned by `buffer` or `buffer_one_sided` into a `Polygon` or `MultiPolygon`, putting each hole in the polygon which contains it.
+pub fn buffer(rings: &[Vec<Vector2>]) -> Geometry {
+	polygons(&polyline::group_polygons(rings.to_vec()))
+}
+
+fn positions(points: &[Vector2]) -> Vec<Vec<f64>> {
+	points.iter().map(|point| vec![point.x, point.y]).collect()
+}
+
+/// Converts positions into points, checking that each has at least `dimension` numbers.
+fn points(positions: &[Vec<f64>], dimension: usize) -> Result<Vec<Vector2>, GeoJsonError> {
+	positions
+		.iter()
+		.map(|position| {
+			if position.len() < dimension {
+				Err(GeoJsonError::WrongDimension { expected: dimension, found: position.len() })
+			} else {
+				Ok(Vector2::new(position[0], position[1]))
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::offset_options::{CapStyle, OffsetOptions};
+
+	#[test]
+	fn line_string_round_trip() {
+		let ls: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(3.0, 0.0), Vector2::new(3.0, 4.0)].into();
+		let json = serde_json::to_string(&Feature::new(ls.to_geojson())).unwrap();
+		assert_eq!(json, r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[0.0,0.0],[3.0,0.0],[3.0,4.0]]},"properties":{}}"#);
+		let feature: Feature = serde_json::from_str(&json).unwrap();
+		assert_eq!(LineStringMeasured::from_geojson(&feature.geometry.unwrap()), Ok(ls.clone()));
+
+		let geometry: Geometry = serde_json::from_str(r#"{"type":"MultiLineString","coordinates":[[[0,0],[1,0]],[[5,5,100],[6,6,100]]]}"#).unwrap();
+		let lines = read_multi_line_string(&geometry).unwrap();
+		assert_eq!(lines[1].into_tuples(), vec![(5.0, 5.0), (6.0, 6.0)]);
+		assert_eq!(multi_line_string(&lines), Geometry::MultiLineString(vec![vec![vec![0.0, 0.0], vec![1.0, 0.0]], vec![vec![5.0, 5.0], vec![6.0, 6.0]]]));
+		assert_eq!(
+			LineStringMeasured::from_geojson(&Geometry::Polygon(vec![])),
+			Err(GeoJsonError::UnsupportedGeometry("Polygon".to_string()))
+		);
+	}
+
+	#[test]
+	fn measures() {
+		let ls: LineStringM = vec![(0.0, 0.0, 10.0), (3.0, 0.0, 13.0), (3.0, 4.0, 17.0)].into();
+		let as_coordinate = ls.to_feature(&Measures::Coordinate);
+		assert_eq!(
+			serde_json::to_string(&as_coordinate.geometry).unwrap(),
+			r#"{"type":"LineString","coordinates":[[0.0,0.0,10.0],[3.0,0.0,13.0],[3.0,4.0,17.0]]}"#
+		);
+		assert_eq!(LineStringM::from_feature(&as_coordinate, &Measures::Coordinate), Ok(ls.clone()));
+
+		let property = Measures::Property("measures".to_string());
+		let as_property = ls.to_feature(&property);
+		assert_eq!(serde_json::to_string(&as_property.properties).unwrap(), r#"{"measures":[10.0,13.0,17.0]}"#);
+		let feature: Feature = serde_json::from_str(&serde_json::to_string(&as_property).unwrap()).unwrap();
+		assert_eq!(LineStringM::from_feature(&feature, &property), Ok(ls));
+
+		assert_eq!(LineStringM::from_feature(&feature, &Measures::Coordinate), Err(GeoJsonError::WrongDimension { expected: 3, found: 2 }));
+		assert_eq!(LineStringM::from_feature(&feature, &Measures::Property("chainage".to_string())), Err(GeoJsonError::MissingMeasures));
+	}
+
+	#[test]
+	fn buffer_polygon() {
+		let ls: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0)].into();
+		let rings = ls.buffer(1.0, CapStyle::Flat, &OffsetOptions::default()).unwrap();
+		match buffer(&rings) {
+			Geometry::Polygon(polygon) => {
+				assert_eq!(polygon.len(), 1);
+				assert_eq!(polygon[0].first(), polygon[0].last());
+			}
+			other => panic!("expected a polygon but got {:?}", other),
+		}
+		let collection = FeatureCollection { features: vec![Feature::new(buffer(&rings)), Feature::new(ls.to_geojson())] };
+		let json = serde_json::to_string(&collection).unwrap();
+		assert!(json.starts_with(r#"{"type":"FeatureCollection","features":[{"type":"Feature","geometry":{"type":"Polygon","#));
+		assert_eq!(serde_json::from_str::<FeatureCollection>(&json).unwrap(), collection);
+	}
+
+	#[test]
+	fn serialise_line_strings() {
+		let ls: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(3.0, 4.0)].into();
+		assert_eq!(serde_json::to_string(&ls).unwrap(), "[[0.0,0.0],[3.0,4.0]]");
+		assert_eq!(serde_json::from_str::<LineStringMeasured>("[[0.0,0.0],[3.0,4.0]]").unwrap(), ls);
+		assert_eq!(serde_json::to_string(&ls.segments[0]).unwrap(), r#"{"a":[0.0,0.0],"b":[3.0,4.0],"mag":5.0}"#);
+		let ls_m = LineStringM::from_line_string(&ls, 0.0, 10.0);
+		assert_eq!(serde_json::to_string(&ls_m).unwrap(), "[[0.0,0.0,0.0],[3.0,4.0,10.0]]");
+		assert_eq!(serde_json::from_str::<LineStringM>("[[0.0,0.0,0.0],[3.0,4.0,10.0]]").unwrap(), ls_m);
+	}
+}
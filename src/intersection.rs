@@ -0,0 +1,369 @@
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::line_segment_measured::LineSegmentMeasured;
+use crate::polyline;
+
+/// A point where two linestrings cross, and how far along each it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineIntersection {
+	pub point: Vector2,
+	pub fraction_of_length: f64,
+	pub distance_along: f64,
+	pub other_fraction_of_length: f64,
+	pub other_distance_along: f64,
+}
+
+/// A place where a linestring meets itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelfIntersection {
+	/// The linestring crosses or touches itself at `point`, passing it first at `fraction_of_length` and again at `other_fraction_of_length`.
+	Crossing { point: Vector2, fraction_of_length: f64, other_fraction_of_length: f64 },
+	/// The linestring runs over the same ground twice. The second pass starts at the same point as the first,
+	/// and runs backwards if `other_fraction_of_length_start` is greater than `other_fraction_of_length_end`.
+	Overlap {
+		fraction_of_length_start: f64,
+		fraction_of_length_end: f64,
+		other_fraction_of_length_start: f64,
+		other_fraction_of_length_end: f64,
+	},
+}
+
+impl SelfIntersection {
+	/// The two fractions of length, in order, at which the linestring is at the same point. For an overlap this is where it starts.
+	fn first_and_second(&self) -> (f64, f64) {
+		let (first, second) = match *self {
+			SelfIntersection::Crossing { fraction_of_length, other_fraction_of_length, .. } => (fraction_of_length, other_fraction_of_length),
+			SelfIntersection::Overlap { fraction_of_length_start, other_fraction_of_length_start, .. } => {
+				(fraction_of_length_start, other_fraction_of_length_start)
+			}
+		};
+		(first.min(second), first.max(second))
+	}
+}
+
+impl LineStringMeasured {
+	/// Finds every point where this linestring crosses or touches `other`, sorted by distance along this linestring.
+	/// A crossing at a vertex is only reported once. Parts where the linestrings run along each other are not reported.
+	///
+	/// Candidate segment pairs are found with a sweep line, so long linestrings are not compared segment by segment.
+	pub fn intersections(&self, other: &LineStringMeasured) -> Vec<LineIntersection> {
+		let mut result: Vec<LineIntersection> = polyline::overlapping_pairs(&self.segments, &other.segments)
+			.into_iter()
+			.filter_map(|(i, j)| {
+				let (point, time, other_time) = self.segments[i].intersect_bounded(&other.segments[j])?;
+				let distance_along = distance_to_segment(self, i) + self.segments[i].mag * time;
+				let other_distance_along = distance_to_segment(other, j) + other.segments[j].mag * other_time;
+				Some(LineIntersection {
+					point,
+					fraction_of_length: if self.mag > 0f64 { distance_along / self.mag } else { 0f64 },
+					distance_along,
+					other_fraction_of_length: if other.mag > 0f64 { other_distance_along / other.mag } else { 0f64 },
+					other_distance_along,
+				})
+			})
+			.collect();
+		sort_and_dedup(&mut result, tolerance(self.mag + other.mag));
+		result
+	}
+}
+
+impl LineStringMeasured {
+	/// Finds every place where the linestring crosses, touches or runs back over itself, sorted by where they first occur.
+	/// Segments which meet end to end are not reported, including the start and end of a closed linestring.
+	pub fn self_intersections(&self) -> Vec<SelfIntersection> {
+		if self.mag == 0f64 {
+			return Vec::new();
+		}
+		let tolerance = tolerance(self.mag);
+		let along = |index: usize, time: f64| distance_to_segment(self, index) + self.segments[index].mag * time;
+		let last = self.segments.len() - 1;
+
+		// found as distances along the linestring, so that they can be compared with the same tolerance as the geometry
+		let mut crossings: Vec<(Vector2, f64, f64)> = Vec::new();
+		let mut overlaps: Vec<(f64, f64, f64, f64)> = Vec::new();
+		for (i, j) in polyline::overlapping_self_pairs(&self.segments) {
+			let (segment, other) = (&self.segments[i], &self.segments[j]);
+			if let Some((start, end, other_start, other_end)) = collinear_overlap(segment, other, tolerance) {
+				overlaps.push((along(i, start), along(i, end), along(j, other_start), along(j, other_end)));
+			} else if j > i + 1 {
+				if let Some((point, time, other_time)) = segment.intersect_bounded(other) {
+					if !(self.is_closed() && i == 0 && j == last && point.distance_to(segment.a) <= tolerance) {
+						crossings.push((point, along(i, time), along(j, other_time)));
+					}
+				}
+			}
+		}
+
+		// join overlaps which carry on from one pair of segments to the next
+		overlaps.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.2.total_cmp(&b.2)));
+		let mut joined: Vec<(f64, f64, f64, f64)> = Vec::new();
+		for overlap in overlaps {
+			match joined.iter_mut().find(|previous| (previous.1 - overlap.0).abs() <= tolerance && (previous.3 - overlap.2).abs() <= tolerance) {
+				Some(previous) => {
+					previous.1 = overlap.1;
+					previous.3 = overlap.3;
+				}
+				None => joined.push(overlap),
+			}
+		}
+
+		// the ends of an overlap, and crossings on either side of a vertex, are found more than once
+		let within = |value: f64, a: f64, b: f64| a.min(b) - tolerance <= value && value <= a.max(b) + tolerance;
+		crossings.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.2.total_cmp(&b.2)));
+		crossings.dedup_by(|b, a| (a.1 - b.1).abs() <= tolerance && (a.2 - b.2).abs() <= tolerance);
+		crossings.retain(|&(_, first, second)| {
+			!joined.iter().any(|&(start, end, other_start, other_end)| {
+				(within(first, start, end) && within(second, other_start, other_end)) || (within(second, start, end) && within(first, other_start, other_end))
+			})
+		});
+
+		let fraction = |distance_along: f64| distance_along / self.mag;
+		let mut result: Vec<SelfIntersection> = crossings
+			.into_iter()
+			.map(|(point, first, second)| SelfIntersection::Crossing {
+				point,
+				fraction_of_length: fraction(first),
+				other_fraction_of_length: fraction(second),
+			})
+			.chain(joined.into_iter().map(|(start, end, other_start, other_end)| SelfIntersection::Overlap {
+				fraction_of_length_start: fraction(start),
+				fraction_of_length_end: fraction(end),
+				other_fraction_of_length_start: fraction(other_start),
+				other_fraction_of_length_end: fraction(other_end),
+			}))
+			.collect();
+		result.sort_by(|a, b| {
+			let ((a_first, a_second), (b_first, b_second)) = (a.first_and_second(), b.first_and_second());
+			a_first.total_cmp(&b_first).then(a_second.total_cmp(&b_second))
+		});
+		result
+	}
+
+	/// Splits the linestring at both ends of every self intersection, so that no piece crosses or overlaps itself.
+	/// Where the linestring runs over the same ground twice, both passes are kept as separate pieces.
+	pub fn split_at_self_intersections(&self) -> Vec<LineStringMeasured> {
+		let fractions: Vec<f64> = self
+			.self_intersections()
+			.iter()
+			.flat_map(|intersection| match *intersection {
+				SelfIntersection::Crossing { fraction_of_length, other_fraction_of_length, .. } => vec![fraction_of_length, other_fraction_of_length],
+				SelfIntersection::Overlap {
+					fraction_of_length_start,
+					fraction_of_length_end,
+					other_fraction_of_length_start,
+					other_fraction_of_length_end,
+				} => vec![fraction_of_length_start, fraction_of_length_end, other_fraction_of_length_start, other_fraction_of_length_end],
+			})
+			.collect();
+		self.split_at(&fractions).into_iter().filter(|piece| piece.mag > 0f64).collect()
+	}
+
+	/// Cuts out the loops of the linestring, jumping from the first time it reaches each self intersection to the last.
+	/// Loops inside a loop which has already been cut out are ignored. Useful for cleaning up the output of `offset_basic`.
+	pub fn without_loops(&self) -> LineStringMeasured {
+		let mut kept: Vec<(f64, f64)> = Vec::new();
+		let mut position = 0f64;
+		for (first, second) in self.self_intersections().iter().map(SelfIntersection::first_and_second) {
+			if first >= position {
+				kept.push((position, first));
+				position = second;
+			}
+		}
+		kept.push((position, 1f64));
+
+		let mut points: Vec<Vector2> = Vec::new();
+		for (from, to) in kept {
+			if let Some(piece) = self.substring(from, to).filter(|piece| piece.mag > 0f64) {
+				let piece_points: Vec<Vector2> = (&piece).into();
+				let skip = match points.last() {
+					Some(&last) if last.distance_to(piece_points[0]) <= tolerance(self.mag) => 1,
+					_ => 0,
+				};
+				points.extend_from_slice(&piece_points[skip..]);
+			}
+		}
+		points.into()
+	}
+}
+
+/// If the segments lie along the same line and share some length, returns the times along `segment` where they start and stop sharing it,
+/// followed by the times along `other` of the same two points.
+fn collinear_overlap(segment: &LineSegmentMeasured, other: &LineSegmentMeasured, tolerance: f64) -> Option<(f64, f64, f64, f64)> {
+	if segment.mag == 0f64 || other.mag == 0f64 {
+		return None;
+	}
+	let (time_a, offset_a) = segment.project_unbounded(other.a);
+	let (time_b, offset_b) = segment.project_unbounded(other.b);
+	if offset_a.abs() > tolerance || offset_b.abs() > tolerance {
+		return None;
+	}
+	let start = time_a.min(time_b).max(0f64);
+	let end = time_a.max(time_b).min(1f64);
+	if (end - start) * segment.mag <= tolerance {
+		return None;
+	}
+	let other_time = |time: f64| (time - time_a) / (time_b - time_a);
+	Some((start, end, other_time(start), other_time(end)))
+}
+
+/// The distance within which points and distances along are treated as the same, relative to the length of the lines involved.
+fn tolerance(length: f64) -> f64 {
+	length * 1e-12
+}
+
+fn distance_to_segment(line: &LineStringMeasured, index: usize) -> f64 {
+	index.checked_sub(1).map_or(0f64, |previous| line.cumulative[previous])
+}
+
+/// Sorts intersections along the first line, dropping any found twice on the segments either side of a vertex.
+fn sort_and_dedup(intersections: &mut Vec<LineIntersection>, tolerance: f64) {
+	intersections.sort_by(|a, b| a.distance_along.total_cmp(&b.distance_along).then(a.other_distance_along.total_cmp(&b.other_distance_along)));
+	intersections.dedup_by(|b, a| {
+		(a.distance_along - b.distance_along).abs() <= tolerance && (a.other_distance_along - b.other_distance_along).abs() <= tolerance
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn crossings() {
+		let zigzag: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(2.0, 2.0), Vector2::new(4.0, 0.0), Vector2::new(6.0, 2.0)].into();
+		let road: LineStringMeasured = vec![Vector2::new(6.0, 1.0), Vector2::new(0.0, 1.0)].into();
+		let found = zigzag.intersections(&road);
+		let points: Vec<Vector2> = found.iter().map(|intersection| intersection.point).collect();
+		assert_eq!(points, vec![Vector2::new(1.0, 1.0), Vector2::new(3.0, 1.0), Vector2::new(5.0, 1.0)]);
+		assert!((found[1].fraction_of_length - 0.5).abs() < 1e-12);
+		assert!((found[0].other_fraction_of_length - 5.0 / 6.0).abs() < 1e-12);
+		assert_eq!(found[2].other_distance_along, 1.0);
+
+		// the other way round is sorted along the road instead
+		let found = road.intersections(&zigzag);
+		assert_eq!(found[0].point, Vector2::new(5.0, 1.0));
+		assert!((found[0].other_fraction_of_length - 5.0 / 6.0).abs() < 1e-12);
+	}
+
+	#[test]
+	fn crossing_at_a_vertex() {
+		let vee: LineStringMeasured = vec![Vector2::new(0.0, 2.0), Vector2::new(2.0, 0.0), Vector2::new(4.0, 2.0)].into();
+		let cross: LineStringMeasured = vec![Vector2::new(2.0, -2.0), Vector2::new(2.0, 0.0), Vector2::new(2.0, 2.0)].into();
+		let found = vee.intersections(&cross);
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].point, Vector2::new(2.0, 0.0));
+		assert_eq!(found[0].fraction_of_length, 0.5);
+		assert_eq!(found[0].other_fraction_of_length, 0.5);
+
+		let far_away: LineStringMeasured = vec![Vector2::new(10.0, 0.0), Vector2::new(10.0, 5.0)].into();
+		assert_eq!(vee.intersections(&far_away), vec![]);
+	}
+
+	#[test]
+	fn self_crossing() {
+		// east, north, west, then south across the first segment
+		let line: LineStringMeasured =
+			vec![Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0), Vector2::new(4.0, 2.0), Vector2::new(2.0, 2.0), Vector2::new(2.0, -2.0)].into();
+		assert_eq!(
+			line.self_intersections(),
+			vec![SelfIntersection::Crossing { point: Vector2::new(2.0, 0.0), fraction_of_length: 2.0 / 12.0, other_fraction_of_length: 10.0 / 12.0 }]
+		);
+		let pieces: Vec<Vec<(f64, f64)>> = line.split_at_self_intersections().iter().map(|piece| piece.into_tuples()).collect();
+		assert_eq!(
+			pieces,
+			vec![
+				vec![(0.0, 0.0), (2.0, 0.0)],
+				vec![(2.0, 0.0), (4.0, 0.0), (4.0, 2.0), (2.0, 2.0), (2.0, 0.0)],
+				vec![(2.0, 0.0), (2.0, -2.0)],
+			]
+		);
+		assert_eq!(line.without_loops().into_tuples(), vec![(0.0, 0.0), (2.0, 0.0), (2.0, -2.0)]);
+	}
+
+	#[test]
+	fn self_overlap() {
+		// a spike which runs back over the last two thirds of the line
+		let line: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(3.0, 0.0), Vector2::new(6.0, 0.0), Vector2::new(1.0, 0.0)].into();
+		assert_eq!(
+			line.self_intersections(),
+			vec![SelfIntersection::Overlap {
+				fraction_of_length_start: 1.0 / 11.0,
+				fraction_of_length_end: 6.0 / 11.0,
+				other_fraction_of_length_start: 1.0,
+				other_fraction_of_length_end: 6.0 / 11.0,
+			}]
+		);
+		assert_eq!(line.split_at_self_intersections().len(), 3);
+		assert_eq!(line.without_loops().into_tuples(), vec![(0.0, 0.0), (1.0, 0.0)]);
+	}
+
+	#[test]
+	fn self_overlap_far_from_the_origin() {
+		// the overlap is found on several pairs of segments, whose ends only agree to within rounding
+		let x = 1e6;
+		let line: LineStringMeasured =
+			vec![Vector2::new(x, 0.0), Vector2::new(x + 0.3, 0.0), Vector2::new(x + 0.7, 0.0), Vector2::new(x + 1.1, 0.0), Vector2::new(x + 0.1, 0.0)].into();
+		let found = line.self_intersections();
+		assert_eq!(found.len(), 1);
+		match found[0] {
+			SelfIntersection::Overlap { fraction_of_length_start, fraction_of_length_end, other_fraction_of_length_start, other_fraction_of_length_end } => {
+				assert!((fraction_of_length_start - 0.1 / 2.1).abs() < 1e-9);
+				assert!((fraction_of_length_end - 1.1 / 2.1).abs() < 1e-9);
+				assert!((other_fraction_of_length_start - 1.0).abs() < 1e-9);
+				assert!((other_fraction_of_length_end - 1.1 / 2.1).abs() < 1e-9);
+			}
+			crossing => panic!("expected an overlap, found {:?}", crossing),
+		}
+		assert_eq!(line.without_loops().segments.len(), 1);
+	}
+
+	#[test]
+	fn simple_lines() {
+		let square: LineStringMeasured =
+			vec![Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(1.0, 1.0), Vector2::new(0.0, 1.0), Vector2::new(0.0, 0.0)].into();
+		assert_eq!(square.self_intersections(), vec![]);
+		assert_eq!(square.split_at_self_intersections(), vec![square.clone()]);
+		assert_eq!(square.without_loops(), square);
+	}
+
+	#[test]
+	fn offset_loops() {
+		// the inside of a short zigzag loops back on itself when offset
+		let line: LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0), Vector2::new(10.0, 1.0), Vector2::new(20.0, 1.0)].into();
+		let offset: LineStringMeasured = line.offset_basic(-3.0).unwrap().into();
+		assert!(!offset.self_intersections().is_empty());
+		let cleaned = offset.without_loops();
+		assert_eq!(cleaned.self_intersections(), vec![]);
+		assert_eq!(cleaned.split_at_self_intersections(), vec![cleaned.clone()]);
+	}
+
+	#[test]
+	fn sweep_finds_the_same_pairs_as_checking_every_pair() {
+		let spiral: Vec<Vector2> = (0..200).map(|i| Vector2::new((i as f64 * 0.3).cos(), (i as f64 * 0.3).sin()) * (i as f64 * 0.05)).collect();
+		let spokes: Vec<Vector2> = (0..50).map(|i| Vector2::new((i as f64 * 2.1).cos(), (i as f64 * 2.1).sin()) * (i as f64 * 0.2)).collect();
+		let (spiral, spokes) = (polyline::segments(&spiral), polyline::segments(&spokes));
+		let mut swept: Vec<(usize, usize)> = polyline::overlapping_pairs(&spiral, &spokes)
+			.into_iter()
+			.filter(|&(i, j)| spiral[i].intersect_bounded(&spokes[j]).is_some())
+			.collect();
+		swept.sort();
+		let every_pair: Vec<(usize, usize)> = (0..spiral.len())
+			.flat_map(|i| (0..spokes.len()).map(move |j| (i, j)))
+			.filter(|&(i, j)| spiral[i].intersect_bounded(&spokes[j]).is_some())
+			.collect();
+		assert!(!every_pair.is_empty());
+		assert_eq!(swept, every_pair);
+
+		let mut swept_self = polyline::overlapping_self_pairs(&spokes);
+		swept_self.sort();
+		let every_self_pair: Vec<(usize, usize)> = (0..spokes.len())
+			.flat_map(|i| (i + 1..spokes.len()).map(move |j| (i, j)))
+			.filter(|&(i, j)| spokes[i].intersect_bounded(&spokes[j]).is_some())
+			.collect();
+		assert_eq!(swept_self.into_iter().filter(|&(i, j)| spokes[i].intersect_bounded(&spokes[j]).is_some()).collect::<Vec<_>>(), every_self_pair);
+
+		// segments with NaN ends are skipped rather than breaking the sort
+		let with_nan = polyline::segments(&[Vector2::new(0.0, 0.0), Vector2::new(f64::NAN, 0.0), Vector2::new(1.0, 1.0), Vector2::new(0.0, 1.0)]);
+		assert_eq!(polyline::overlapping_self_pairs(&with_nan), vec![]);
+		assert!(polyline::overlapping_pairs(&with_nan, &spokes).iter().all(|&(i, _)| i == 2));
+	}
+}
@@ -3,7 +3,26 @@ pub mod vector2;
 // pub mod LineStringy;
 // pub mod LineString;
 pub mod line_string_measured;
+pub mod line_string_m;
+pub mod multi_line_string_measured;
 pub mod line_segment_measured;
+pub mod offset_options;
+pub mod located_point;
+pub mod error;
+pub mod validation_report;
+pub mod wkt;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+pub mod wkb;
+pub mod network;
+pub mod route_events;
+pub mod event_overlay;
+pub mod intersection;
+#[cfg(feature = "geo-types")]
+pub mod geo_interop;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+mod polyline;
 
 #[cfg(test)]
 pub mod tests;
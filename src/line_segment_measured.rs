@@ -1,6 +1,7 @@
 use crate::vector2::Vector2;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LineSegmentMeasured {
 	pub a: Vector2,
 	pub b: Vector2,
@@ -12,6 +13,7 @@ pub struct LineSegmentMeasured {
 // }
 
 impl LineSegmentMeasured {
+	#[allow(clippy::op_ref, clippy::needless_borrow)]
 	pub fn intersect(&self, other: &LineSegmentMeasured) -> Option<(Vector2, f64, f64)> {
 		let LineSegmentMeasured { a, b, mag: _ab_len } = self;
 		let ab = b - a;
@@ -32,4 +34,30 @@ impl LineSegmentMeasured {
 
 		Some((a + &(ab * time_ab), time_ab, time_cd))
 	}
+
+	/// Like `intersect`, but only returns an intersection if it lies within both segments.
+	pub fn intersect_bounded(&self, other: &LineSegmentMeasured) -> Option<(Vector2, f64, f64)> {
+		self.intersect(other).filter(|(_, time_ab, time_cd)| {
+			(0f64..=1f64).contains(time_ab) && (0f64..=1f64).contains(time_cd)
+		})
+	}
+
+	/// Returns the closest point on the segment, and its time along the segment.
+	pub fn project(&self, point: Vector2) -> (Vector2, f64) {
+		if self.mag == 0f64 {
+			return (self.a, 0f64);
+		}
+		let ab = self.b - self.a;
+		let time = ((point - self.a).dot(ab) / ab.magnitude_squared()).clamp(0f64, 1f64);
+		(self.a + ab * time, time)
+	}
+
+	/// Returns the unclamped time along this segment of the closest point on the infinite line through `a` and `b`,
+	/// and the perpendicular distance from that line (positive to the left).
+	pub fn project_unbounded(&self, point: Vector2) -> (f64, f64) {
+		let ab = self.b - self.a;
+		let ap = point - self.a;
+		let mag_squared = ab.magnitude_squared();
+		(ap.dot(ab) / mag_squared, ab.cross(ap) / mag_squared.sqrt())
+	}
 }
\ No newline at end of file
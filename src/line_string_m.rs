@@ -0,0 +1,256 @@
+use crate::vector2::Vector2;
+use crate::line_segment_measured::LineSegmentMeasured;
+use crate::line_string_measured::LineStringMeasured;
+use crate::offset_options::OffsetOptions;
+use crate::error::LineStringError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A linestring with a calibrated measure (M value) at each vertex.
+///
+/// Measures do not need to be proportional to length. Measures should increase (or decrease) along each part of the line,
+/// but may jump at equations and gaps; a jump is represented by two vertices at the same location with different measures.
+/// Where a measure occurs more than once, the first occurrence is used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineStringM {
+	/// Read it with `line()`; it is private so that there is always one measure per vertex.
+	pub(crate) line: LineStringMeasured,
+	/// One measure per vertex of `line`. Read it with `measures()`; it is private so that `order` stays in sync.
+	pub(crate) measures: Vec<f64>,
+	/// Whether `measures` can be binary searched
+	order: MeasureOrder,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MeasureOrder {
+	/// Never decreasing
+	Increasing,
+	/// Never increasing
+	Decreasing,
+	/// Both, or contains NaN
+	Mixed,
+}
+
+impl MeasureOrder {
+	fn of(measures: &[f64]) -> MeasureOrder {
+		if measures.windows(2).all(|pair| pair[0] <= pair[1]) {
+			MeasureOrder::Increasing
+		} else if measures.windows(2).all(|pair| pair[0] >= pair[1]) {
+			MeasureOrder::Decreasing
+		} else {
+			MeasureOrder::Mixed
+		}
+	}
+}
+
+impl From<&Vec<(f64, f64, f64)>> for LineStringM {
+	fn from(other: &Vec<(f64, f64, f64)>) -> Self {
+		let points: Vec<Vector2> = other.iter().map(|&(x, y, _)| Vector2::new(x, y)).collect();
+		LineStringM::from_parts(points.into(), other.iter().map(|&(_, _, m)| m).collect())
+	}
+}
+
+impl From<Vec<(f64, f64, f64)>> for LineStringM {
+	fn from(other: Vec<(f64, f64, f64)>) -> Self {
+		(&other).into()
+	}
+}
+
+/// Serialised as a list of `(x, y, m)` tuples.
+impl Serialize for LineStringM {
+	fn serialize<S>(&self, serialiser: S) -> Result<S::Ok, S::Error>
+	where S: Serializer {
+		self.into_tuples_measured().serialize(serialiser)
+	}
+}
+
+impl<'de> Deserialize<'de> for LineStringM {
+	fn deserialize<D>(deserialiser: D) -> Result<Self, D::Error>
+	where D: Deserializer<'de> {
+		Ok(Vec::<(f64, f64, f64)>::deserialize(deserialiser)?.into())
+	}
+}
+
+impl LineStringM {
+	/// Every constructor goes through here so that `order` is always calculated. Assumes one measure per vertex.
+	pub(crate) fn from_parts(line: LineStringMeasured, measures: Vec<f64>) -> LineStringM {
+		let order = MeasureOrder::of(&measures);
+		LineStringM { line, measures, order }
+	}
+
+	/// Returns `None` unless there is exactly one measure per point.
+	pub fn new(points: Vec<Vector2>, measures: Vec<f64>) -> Option<LineStringM> {
+		if points.len() != measures.len() {
+			return None;
+		}
+		Some(LineStringM::from_parts(points.into(), measures))
+	}
+
+	/// Same as `new`, but reports why the points and measures could not be combined.
+	pub fn try_new(points: Vec<Vector2>, measures: Vec<f64>) -> Result<LineStringM, LineStringError> {
+		if points.len() != measures.len() {
+			return Err(LineStringError::MeasureCountMismatch { points: points.len(), measures: measures.len() });
+		}
+		if measures.iter().any(|measure| !measure.is_finite()) {
+			return Err(LineStringError::NonFinite);
+		}
+		Ok(LineStringM::from_parts(points.into(), measures))
+	}
+
+	/// Calibrates a linestring with measures proportional to length, the same way `into_tuples_measured` does.
+	pub fn from_line_string(line: &LineStringMeasured, from_measure: f64, to_measure: f64) -> LineStringM {
+		line.into_tuples_measured(from_measure, to_measure).into()
+	}
+
+	pub fn into_tuples_measured(&self) -> Vec<(f64, f64, f64)> {
+		self.line
+			.into_tuples()
+			.into_iter()
+			.zip(self.measures.iter())
+			.map(|((x, y), &m)| (x, y, m))
+			.collect()
+	}
+
+	/// The linestring without its measures.
+	pub fn line(&self) -> &LineStringMeasured {
+		&self.line
+	}
+
+	/// One measure per vertex of `line`.
+	pub fn measures(&self) -> &[f64] {
+		&self.measures
+	}
+
+	pub fn from_measure(&self) -> Option<f64> {
+		self.measures.first().copied()
+	}
+
+	pub fn to_measure(&self) -> Option<f64> {
+		self.measures.last().copied()
+	}
+
+	/// Returns the index of the first segment whose measures span `measure`, and the time along that segment.
+	/// Binary searches if the measures only increase or only decrease, and otherwise checks each segment in turn.
+	fn find_measure(&self, measure: f64) -> Option<(usize, f64)> {
+		let spans = |index: usize| -> Option<(usize, f64)> {
+			let (measure_a, measure_b) = (*self.measures.get(index)?, *self.measures.get(index + 1)?);
+			if measure_a.min(measure_b) <= measure && measure <= measure_a.max(measure_b) {
+				let time = if measure_a == measure_b { 0f64 } else { (measure - measure_a) / (measure_b - measure_a) };
+				Some((index, time))
+			} else {
+				None
+			}
+		};
+		// the segment ending at the first vertex which is not before `measure`
+		let first_not_before = |index: usize| spans(index.saturating_sub(1));
+		match self.order {
+			MeasureOrder::Increasing => first_not_before(self.measures.partition_point(|&m| m < measure)),
+			MeasureOrder::Decreasing => first_not_before(self.measures.partition_point(|&m| m > measure)),
+			MeasureOrder::Mixed => (0..self.measures.len().saturating_sub(1)).find_map(spans),
+		}
+	}
+
+	/// Converts a measure into a distance along the line. Returns `None` if the measure is not on the line.
+	pub fn distance_along_at_measure(&self, measure: f64) -> Option<f64> {
+		let (index, time) = self.find_measure(measure)?;
+		let distance_to_segment = index.checked_sub(1).map_or(0f64, |previous| self.line.cumulative[previous]);
+		Some(distance_to_segment + self.line.segments[index].mag * time)
+	}
+
+	/// Converts a distance along the line into a measure. The distance is clamped to the length of the line.
+	/// Returns `None` if the line has fewer than two points.
+	pub fn measure_at_distance_along(&self, distance_along: f64) -> Option<f64> {
+		let index = self.line.cumulative.partition_point(|&distance_to_end| distance_to_end < distance_along);
+		match self.line.segments.get(index) {
+			Some(segment) => {
+				let distance_to_segment = index.checked_sub(1).map_or(0f64, |previous| self.line.cumulative[previous]);
+				let distance_remaining = distance_along - distance_to_segment;
+				let time = if segment.mag > 0f64 { distance_remaining.max(0f64) / segment.mag } else { 0f64 };
+				Some(self.measures[index] + (self.measures[index + 1] - self.measures[index]) * time)
+			}
+			None => self.line.segments.last().and(self.to_measure()),
+		}
+	}
+
+	/// Gets the point at some measure. Returns `None` if the measure is not on the line.
+	pub fn interpolate(&self, measure: f64) -> Option<Vector2> {
+		let (index, time) = self.find_measure(measure)?;
+		let LineSegmentMeasured { a, b, mag: _ } = &self.line.segments[index];
+		Some(*a + (b - a) * time)
+	}
+
+	/// Gets the direction in radians of the line at some measure. Returns `None` if the measure is not on the line.
+	pub fn direction(&self, measure: f64) -> Option<f64> {
+		let (index, _) = self.find_measure(measure)?;
+		let LineSegmentMeasured { a, b, mag: _ } = &self.line.segments[index];
+		Some((b - a).direction())
+	}
+
+	/// Finds the closest point on the line, and its measure.
+	pub fn locate(&self, point: Vector2) -> Option<(Vector2, f64)> {
+		let located = self.line.locate(point)?;
+		Some((located.point, self.measure_at_distance_along(located.distance_along)?))
+	}
+
+	/// Splits the line at a measure. Measures before the start of the line return `(None, Some(self.clone()))`
+	/// and measures after the end return `(Some(self.clone()), None)`.
+	pub fn cut(&self, measure: f64) -> (Option<LineStringM>, Option<LineStringM>) {
+		let (from_measure, to_measure) = match (self.from_measure(), self.to_measure()) {
+			(Some(from_measure), Some(to_measure)) if !self.line.segments.is_empty() => (from_measure, to_measure),
+			_ => return (None, None),
+		};
+		match self.find_measure(measure) {
+			Some((0, time)) if time <= 0f64 => (None, Some(self.clone())),
+			Some((index, time)) if index == self.line.segments.len() - 1 && time >= 1f64 => (Some(self.clone()), None),
+			Some((index, time)) => {
+				let points: Vec<Vector2> = (&self.line).into();
+				if time >= 1f64 {
+					// exactly on a vertex
+					return (
+						LineStringM::new(points[..index + 2].to_vec(), self.measures[..index + 2].to_vec()),
+						LineStringM::new(points[index + 1..].to_vec(), self.measures[index + 1..].to_vec()),
+					);
+				}
+				let LineSegmentMeasured { a, b, mag: _ } = &self.line.segments[index];
+				let cut_point = *a + (b - a) * time;
+
+				let mut first_points = points[..index + 1].to_vec();
+				let mut first_measures = self.measures[..index + 1].to_vec();
+				first_points.push(cut_point);
+				first_measures.push(measure);
+
+				let mut second_points = vec![cut_point];
+				let mut second_measures = vec![measure];
+				second_points.extend_from_slice(&points[index + 1..]);
+				second_measures.extend_from_slice(&self.measures[index + 1..]);
+				(
+					LineStringM::new(first_points, first_measures),
+					LineStringM::new(second_points, second_measures),
+				)
+			}
+			None if (measure - from_measure).abs() <= (measure - to_measure).abs() => (None, Some(self.clone())),
+			None => (Some(self.clone()), None),
+		}
+	}
+
+	/// Returns the part of the line between two measures, or `None` if that part is empty.
+	pub fn cut_twice(&self, from_measure: f64, to_measure: f64) -> Option<LineStringM> {
+		let (_, after_start) = self.cut(from_measure);
+		let (between, _) = after_start?.cut(to_measure);
+		between
+	}
+
+	/// Offsets the part of the line between two measures.
+	pub fn offset_basic(&self, from_measure: f64, to_measure: f64, distance: f64, options: &OffsetOptions) -> Option<Vec<Vector2>> {
+		self.cut_twice(from_measure, to_measure)?.line.offset_basic_with_options(distance, options)
+	}
+
+	/// Offsets the line by a distance which is a function of measure.
+	/// `distance` is called with the measure at each vertex, and the offset varies linearly between vertices.
+	pub fn offset_basic_variable<F: Fn(f64) -> f64>(&self, distance: F, options: &OffsetOptions) -> Option<Vec<Vector2>> {
+		let mag = self.line.magnitude();
+		self.line.offset_basic_variable(
+			|fraction_of_length| distance(self.measure_at_distance_along(fraction_of_length * mag).unwrap_or(0f64)),
+			options,
+		)
+	}
+}
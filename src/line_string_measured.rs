@@ -1,10 +1,19 @@
 use crate::vector2::Vector2;
 use crate::line_segment_measured::LineSegmentMeasured;
+use crate::polyline;
+use crate::located_point::{LocatedPoint, Side};
+use crate::offset_options::{OffsetOptions, CapStyle, interpolate_breakpoints};
+use crate::error::LineStringError;
+use crate::validation_report::ValidationReport;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct LineStringMeasured {
 	pub segments: Vec<LineSegmentMeasured>,
 	pub mag: f64,
+	/// The distance from the start of the linestring to the end of each segment, used to find positions by binary search.
+	/// It is calculated by every constructor; use `from_segments` rather than modifying `segments` directly so that it stays up to date.
+	pub(crate) cumulative: Vec<f64>,
 }
 
 impl From<&Vec<Vector2>> for LineStringMeasured {
@@ -13,18 +22,22 @@ impl From<&Vec<Vector2>> for LineStringMeasured {
 			0|1=>LineStringMeasured {
 					segments: Vec::with_capacity(0),
 					mag: 0f64,
+					cumulative: Vec::with_capacity(0),
 				},	
 			_  => {
 				let mut sum_mag = 0f64;
 				let mut vec_part = Vec::with_capacity(other.len() - 1);
+				let mut cumulative = Vec::with_capacity(other.len() - 1);
 				for (&a, &b) in other.iter().zip(other[1..].iter()) {//pairwise
 					let ab_mag = a.distance_to(b);
 					sum_mag += ab_mag; // modifying sum_mag here feels a bit wrong.
 					vec_part.push(LineSegmentMeasured { a, b, mag: ab_mag });
+					cumulative.push(sum_mag);
 				}
 				LineStringMeasured {
 					segments: vec_part,
 					mag: sum_mag,
+					cumulative,
 				}
 			}
 		}
@@ -37,6 +50,23 @@ impl From<Vec<Vector2>> for LineStringMeasured {
 	}
 }
 
+/// Serialised as a list of points. The lengths are recalculated when deserialising.
+impl Serialize for LineStringMeasured {
+	fn serialize<S>(&self, serialiser: S) -> Result<S::Ok, S::Error>
+	where S: Serializer {
+		let points: Vec<Vector2> = self.into();
+		points.serialize(serialiser)
+	}
+}
+
+impl<'de> Deserialize<'de> for LineStringMeasured {
+	fn deserialize<D>(deserialiser: D) -> Result<Self, D::Error>
+	where D: Deserializer<'de> {
+		Ok(Vec::<Vector2>::deserialize(deserialiser)?.into())
+	}
+}
+
+#[allow(clippy::from_over_into)]
 impl Into<Vec<Vector2>> for &LineStringMeasured {
 	fn into(self) -> Vec<Vector2> {
 		let mut result:Vec<Vector2> = self.segments.iter().map(|seg|seg.a).collect();
@@ -50,6 +80,36 @@ impl Into<Vec<Vector2>> for &LineStringMeasured {
 
 impl LineStringMeasured {
 
+	/// Builds a linestring from segments which have already been measured.
+	pub fn from_segments(segments: Vec<LineSegmentMeasured>) -> LineStringMeasured {
+		let mut sum_mag = 0f64;
+		let cumulative: Vec<f64> = segments
+			.iter()
+			.map(|segment| {
+				sum_mag += segment.mag;
+				sum_mag
+			})
+			.collect();
+		LineStringMeasured {
+			segments,
+			mag: sum_mag,
+			cumulative,
+		}
+	}
+
+	/// The distance from the start of the linestring to the end of each segment.
+	pub fn cumulative(&self) -> &[f64] {
+		&self.cumulative
+	}
+
+	/// The distance from the start of the linestring to the start of the segment at `index`.
+	fn distance_to_segment(&self, index: usize) -> f64 {
+		match index {
+			0 => 0f64,
+			_ => self.cumulative[index - 1],
+		}
+	}
+
 	/// The into trait is difficult to call without a helper function. All this does is call the into trait.
 	#[deprecated(since="1.2.0", note="please use `into_tuples` or `into_tuples_measured` instead")]
 	pub fn into_vector2(&self) -> Vec<Vector2>{
@@ -79,10 +139,68 @@ impl LineStringMeasured {
 		result
 	}
 
+	#[allow(clippy::needless_return)]
 	pub fn magnitude(&self) -> f64 {
 		return self.mag;
 	}
 
+	/// True if the linestring ends where it starts, and has at least two segments.
+	pub fn is_closed(&self) -> bool {
+		match (self.segments.first(), self.segments.last()) {
+			(Some(first), Some(last)) => self.segments.len() > 1 && first.a == last.b,
+			_ => false,
+		}
+	}
+
+	/// Returns a copy of the linestring with an extra segment joining the end back to the start, unless it is already closed.
+	pub fn closed(&self) -> LineStringMeasured {
+		let mut points: Vec<Vector2> = self.into();
+		if !self.is_closed() && points.len() > 1 {
+			points.push(points[0]);
+		}
+		points.into()
+	}
+
+	/// Finds the closest point on the linestring to `point`. Returns `None` if the linestring has fewer than two points.
+	/// Where the closest point is a vertex shared by two segments, the side is taken from the segment the point is further to the side of.
+	pub fn locate(&self, point: Vector2) -> Option<LocatedPoint> {
+		let tolerance = self.mag * 1e-12;
+		let mut best: Option<(usize, Vector2, f64, f64, f64)> = None;
+		for (index, segment) in self.segments.iter().enumerate() {
+			let (closest_point, time) = segment.project(point);
+			let distance_from_line = closest_point.distance_to(point);
+			// the perpendicular distance from the segment, extended past its ends, positive on the left
+			let offset = if segment.mag > 0f64 { (segment.b - segment.a).cross(point - segment.a) / segment.mag } else { 0f64 };
+			let is_better = match best {
+				None => true,
+				Some((_, _, _, best_distance, best_offset)) => {
+					distance_from_line < best_distance - tolerance
+						|| (distance_from_line <= best_distance + tolerance && offset.abs() > best_offset.abs())
+				}
+			};
+			if is_better {
+				best = Some((index, closest_point, time, distance_from_line, offset));
+			}
+		}
+		let (index, closest_point, time, distance_from_line, offset) = best?;
+		let segment = &self.segments[index];
+		let distance_along = self.distance_to_segment(index) + segment.mag * time;
+		Some(LocatedPoint {
+			point: closest_point,
+			fraction_of_length: if self.mag > 0f64 { distance_along / self.mag } else { 0f64 },
+			distance_along,
+			distance_from_line,
+			offset,
+			side: if distance_from_line <= tolerance {
+				Side::On
+			} else if offset >= 0f64 {
+				Side::Left
+			} else {
+				Side::Right
+			},
+		})
+	}
+
 	pub fn offset_segments(&self, distance: f64) -> Vec<LineSegmentMeasured> {
 		self.segments
 			.iter()
@@ -101,69 +219,36 @@ impl LineStringMeasured {
 		&self,
 		fraction_of_length: f64,
 	) -> (Option<LineStringMeasured>, Option<LineStringMeasured>) {
-		let distance_along = self.mag * fraction_of_length;
+		self.cut_at_distance(self.mag * fraction_of_length)
+	}
 
+	/// Same as `cut`, but takes an absolute distance along the linestring.
+	pub fn cut_at_distance(
+		&self,
+		distance_along: f64,
+	) -> (Option<LineStringMeasured>, Option<LineStringMeasured>) {
 		if distance_along <= 0f64 {
 			return (None, Some(self.clone()));
 		} else if distance_along >= self.mag {
 			return (Some(self.clone()), None);
-		} else {
-			let mut distance_so_far = 0f64;
-			let mut distance_remaining = distance_along;
-			for (
-				index,
-				LineSegmentMeasured {
-					a,
-					b,
-					mag: segment_length,
-				},
-			) in self.segments.iter().enumerate()
-			{
-				if distance_remaining <= 0f64 {
-					return (
-						Some(LineStringMeasured {
-							segments: self.segments[..index].to_vec(),
-							mag: distance_along,
-						}),
-						Some(LineStringMeasured {
-							segments: self.segments[index..].to_vec(),
-							mag: self.mag - distance_along,
-						}),
-					);
-				} else if distance_remaining < *segment_length {
-					let ab_unit = (b - a) / *segment_length;
-					let intermediate_point = *a + ab_unit * distance_remaining;
-
-					let mut part_1 = self.segments[..index].to_vec();
-					part_1.push(LineSegmentMeasured {
-						a: *a,
-						b: intermediate_point,
-						mag: distance_remaining,
-					});
-
-					let mut part_2 = vec![LineSegmentMeasured {
-						a: intermediate_point,
-						b: *b,
-						mag: segment_length - distance_remaining,
-					}];
-					part_2.append(&mut (self.segments[index + 1..].to_vec()));
-
-					return (
-						Some(LineStringMeasured {
-							segments: part_1,
-							mag: distance_so_far + distance_remaining,
-						}),
-						Some(LineStringMeasured {
-							segments: part_2,
-							mag: self.mag - distance_so_far - distance_remaining,
-						}),
-					);
-				}
-				distance_so_far += segment_length;
-				distance_remaining -= segment_length
-			}
 		}
-		return (None, None);
+		match (self.position_at_distance(0f64), self.position_at_distance(distance_along), self.position_at_distance(self.mag)) {
+			(Some(start), Some(cut), Some(end)) => (
+				Some(LineStringMeasured::from_segments(self.segments_between(start, cut))),
+				Some(LineStringMeasured::from_segments(self.segments_between(cut, end))),
+			),
+			_ => (None, None),
+		}
+	}
+
+	/// Same as `cut`, but takes a measure, where the linestring is measured from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn cut_at_measure(
+		&self,
+		measure: f64,
+		from_measure: f64,
+		to_measure: f64,
+	) -> (Option<LineStringMeasured>, Option<LineStringMeasured>) {
+		self.cut_at_distance(self.distance_at_measure(measure, from_measure, to_measure))
 	}
 
 	pub fn cut_twice(
@@ -175,116 +260,934 @@ impl LineStringMeasured {
 		Option<LineStringMeasured>,
 		Option<LineStringMeasured>,
 	) {
-		let (a, bc) = self.cut(fraction_of_length_start);
-		match bc {
-			Some(bc) => {
-				let a_fraction_of_length = f64::max(fraction_of_length_start, 0f64);
-				let bc_fraction_of_length = 1f64 - a_fraction_of_length;
-				// if bc_fraction_of_length <= 0f64 {
-				// 	return (a, None, None)
-				// }
-				let (b, c) =
-					bc.cut((fraction_of_length_end - a_fraction_of_length) / bc_fraction_of_length);
-				(a, b, c)
-			}
-			None => (a, None, None),
+		self.cut_twice_at_distance(self.mag * fraction_of_length_start, self.mag * fraction_of_length_end)
+	}
+
+	/// Same as `cut_twice`, but takes absolute distances along the linestring.
+	/// Both cuts are made on the original linestring, so the cut points are exactly those returned by `interpolate_at_distance`.
+	pub fn cut_twice_at_distance(
+		&self,
+		distance_along_start: f64,
+		distance_along_end: f64,
+	) -> (
+		Option<LineStringMeasured>,
+		Option<LineStringMeasured>,
+		Option<LineStringMeasured>,
+	) {
+		let (a, bc) = self.cut_at_distance(distance_along_start);
+		let bc = match bc {
+			Some(bc) => bc,
+			None => return (a, None, None),
+		};
+		let distance_along_start = f64::max(distance_along_start, 0f64);
+		if distance_along_end <= distance_along_start {
+			return (a, None, Some(bc));
+		} else if distance_along_end >= self.mag {
+			return (a, Some(bc), None);
+		}
+		match (
+			self.position_at_distance(distance_along_start),
+			self.position_at_distance(distance_along_end),
+			self.position_at_distance(self.mag),
+		) {
+			(Some(start), Some(cut), Some(end)) => (
+				a,
+				Some(LineStringMeasured::from_segments(self.segments_between(start, cut))),
+				Some(LineStringMeasured::from_segments(self.segments_between(cut, end))),
+			),
+			_ => (a, None, None),
 		}
 	}
 
+	/// Same as `cut_twice`, but takes measures, where the linestring is measured from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn cut_twice_at_measure(
+		&self,
+		measure_start: f64,
+		measure_end: f64,
+		from_measure: f64,
+		to_measure: f64,
+	) -> (
+		Option<LineStringMeasured>,
+		Option<LineStringMeasured>,
+		Option<LineStringMeasured>,
+	) {
+		self.cut_twice_at_distance(
+			self.distance_at_measure(measure_start, from_measure, to_measure),
+			self.distance_at_measure(measure_end, from_measure, to_measure),
+		)
+	}
+
+	/// Returns the part of the linestring between two fractions of its length, which are clamped to `0..=1`.
+	/// If `fraction_of_length_start` is after `fraction_of_length_end` the result runs backwards.
+	/// If they are equal the result is a single segment of zero length at that point.
+	/// Returns `None` only if the linestring has no segments.
+	pub fn substring(&self, fraction_of_length_start: f64, fraction_of_length_end: f64) -> Option<LineStringMeasured> {
+		self.substring_at_distance(self.mag * fraction_of_length_start, self.mag * fraction_of_length_end)
+	}
+
+	/// Same as `substring`, but takes absolute distances along the linestring.
+	pub fn substring_at_distance(&self, distance_along_start: f64, distance_along_end: f64) -> Option<LineStringMeasured> {
+		let distance_along_start = distance_along_start.max(0f64).min(self.mag);
+		let distance_along_end = distance_along_end.max(0f64).min(self.mag);
+		let from = self.position_at_distance(distance_along_start.min(distance_along_end))?;
+		let to = self.position_at_distance(distance_along_start.max(distance_along_end))?;
+		let mut segments = self.segments_between(from, to);
+		if segments.is_empty() {
+			segments.push(LineSegmentMeasured { a: from.2, b: from.2, mag: 0f64 });
+		}
+		let result = LineStringMeasured::from_segments(segments);
+		if distance_along_start > distance_along_end {
+			Some(result.reversed())
+		} else {
+			Some(result)
+		}
+	}
+
+	/// Same as `substring`, but takes measures, where the linestring is measured from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn substring_at_measure(&self, measure_start: f64, measure_end: f64, from_measure: f64, to_measure: f64) -> Option<LineStringMeasured> {
+		self.substring_at_distance(
+			self.distance_at_measure(measure_start, from_measure, to_measure),
+			self.distance_at_measure(measure_end, from_measure, to_measure),
+		)
+	}
+
+	/// Splits the linestring at several fractions of its length in one pass, returning one more piece than there are fractions.
+	/// Fractions are clamped to `0..=1` and sorted. Where two fractions are equal, the piece between them is a single zero length segment.
+	/// Returns an empty `Vec` if the linestring has no segments.
+	pub fn split_at(&self, fractions_of_length: &[f64]) -> Vec<LineStringMeasured> {
+		let distances: Vec<f64> = fractions_of_length.iter().map(|fraction_of_length| self.mag * fraction_of_length).collect();
+		self.split_at_distances(&distances)
+	}
+
+	/// Same as `split_at`, but takes absolute distances along the linestring.
+	pub fn split_at_distances(&self, distances_along: &[f64]) -> Vec<LineStringMeasured> {
+		let mut distances: Vec<f64> = Vec::with_capacity(distances_along.len() + 2);
+		distances.push(0f64);
+		distances.extend(distances_along.iter().map(|distance_along| distance_along.max(0f64).min(self.mag)));
+		distances.push(self.mag);
+		distances[1..].sort_by(|a, b| a.total_cmp(b));
+		let positions = match self.positions_at_sorted_distances(&distances) {
+			Some(positions) => positions,
+			None => return Vec::new(),
+		};
+		positions
+			.iter()
+			.zip(positions.iter().skip(1))
+			.map(|(&from, &to)| {
+				let mut segments = self.segments_between(from, to);
+				if segments.is_empty() {
+					segments.push(LineSegmentMeasured { a: from.2, b: from.2, mag: 0f64 });
+				}
+				LineStringMeasured::from_segments(segments)
+			})
+			.collect()
+	}
+
+	/// Splits the linestring into pieces of length `step`, measured from the start. The last piece may be shorter.
+	/// Returns a copy of the whole linestring if `step` is not positive.
+	pub fn chunks_of_length(&self, step: f64) -> Vec<LineStringMeasured> {
+		if step <= 0f64 || step.is_nan() {
+			return vec![self.clone()];
+		}
+		let distances: Vec<f64> = (1..)
+			.map(|index| step * index as f64)
+			.take_while(|&distance_along| distance_along < self.mag)
+			.collect();
+		self.split_at_distances(&distances)
+	}
+
+	/// Same as `position_at_distance` for many distances, which must be sorted, walking the segments only once.
+	fn positions_at_sorted_distances(&self, distances_along: &[f64]) -> Option<Vec<(usize, f64, Vector2)>> {
+		self.segments.last()?;
+		let mut index = 0;
+		Some(
+			distances_along
+				.iter()
+				.map(|&distance_along| {
+					while index < self.cumulative.len() && self.cumulative[index] <= distance_along {
+						index += 1;
+					}
+					self.position_in_segment(index, distance_along)
+				})
+				.collect(),
+		)
+	}
+
+	/// Returns a copy of the linestring running in the opposite direction.
+	pub fn reversed(&self) -> LineStringMeasured {
+		LineStringMeasured::from_segments(
+			self.segments
+				.iter()
+				.rev()
+				.map(|LineSegmentMeasured { a, b, mag }| LineSegmentMeasured { a: *b, b: *a, mag: *mag })
+				.collect(),
+		)
+	}
+
 	pub fn interpolate(
 		&self,
 		fraction_of_length: f64,
 	) -> Option<Vector2> {
-		if self.segments.len() == 0 {
-			return None
-		}
-		if fraction_of_length <= 0f64{
-			return self.segments.first().and_then(|LineSegmentMeasured{a,b:_, mag:_}| Some(*a))
-		}
-		let de_normalised_distance_along = self.mag * fraction_of_length;
-		let mut len_so_far = 0f64;
-		
-		for LineSegmentMeasured {
-			a,
-			b,
-			mag: segment_length,
-		} in &self.segments
-		{
-			len_so_far += segment_length;
-			if len_so_far >= de_normalised_distance_along {
-				return Some(*b - (b - a) / *segment_length * (len_so_far - de_normalised_distance_along));
+		self.interpolate_at_distance(self.mag * fraction_of_length)
+	}
+
+	/// Same as `interpolate`, but takes an absolute distance along the linestring.
+	pub fn interpolate_at_distance(
+		&self,
+		distance_along: f64,
+	) -> Option<Vector2> {
+		self.position_at_distance(distance_along).map(|(_, _, point)| point)
+	}
+
+	/// Same as `interpolate`, but takes a measure, where the linestring is measured from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn interpolate_at_measure(&self, measure: f64, from_measure: f64, to_measure: f64) -> Option<Vector2> {
+		self.interpolate_at_distance(self.distance_at_measure(measure, from_measure, to_measure))
+	}
+
+	pub fn direction(&self, fraction_of_length: f64) -> f64 {
+		self.direction_at_distance(self.mag * fraction_of_length)
+	}
+
+	/// Same as `direction`, but takes an absolute distance along the linestring.
+	pub fn direction_at_distance(&self, distance_along: f64) -> f64 {
+		let index = self.cumulative.partition_point(|&distance_to_end| distance_to_end < distance_along);
+		match self.segments.get(index) {
+			Some(LineSegmentMeasured { a, b, mag: _ }) => (b - a).direction(),
+			None => 0f64,
+		}
+	}
+
+	/// Same as `direction`, but takes a measure, where the linestring is measured from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn direction_at_measure(&self, measure: f64, from_measure: f64, to_measure: f64) -> f64 {
+		self.direction_at_distance(self.distance_at_measure(measure, from_measure, to_measure))
+	}
+
+	/// Finds the segment containing a distance along the linestring, the distance along that segment, and the point there.
+	/// Distances are clamped to the ends of the linestring. Cut points and interpolated points both come from here so that they agree exactly.
+	fn position_at_distance(&self, distance_along: f64) -> Option<(usize, f64, Vector2)> {
+		self.segments.last()?;
+		let index = self.cumulative.partition_point(|&distance_to_end| distance_to_end <= distance_along);
+		Some(self.position_in_segment(index, distance_along))
+	}
+
+	/// The position at a distance along the linestring, given the index of the first segment which ends after that distance.
+	fn position_in_segment(&self, index: usize, distance_along: f64) -> (usize, f64, Vector2) {
+		if distance_along <= 0f64 {
+			return (0, 0f64, self.segments[0].a);
+		}
+		match self.segments.get(index) {
+			Some(LineSegmentMeasured { a, b, mag: segment_length }) => {
+				let distance_remaining = distance_along - self.distance_to_segment(index);
+				if distance_remaining <= 0f64 {
+					(index, 0f64, *a)
+				} else {
+					(index, distance_remaining, *a + (b - a) / *segment_length * distance_remaining)
+				}
+			}
+			None => {
+				let last = &self.segments[self.segments.len() - 1];
+				(self.segments.len() - 1, last.mag, last.b)
 			}
 		}
-		self.segments.last().and_then(|LineSegmentMeasured{a:_ , b, mag:_}| Some(*b))
 	}
 
-	pub fn direction(&self, fraction_of_length: f64) -> f64 {
-		let de_normalised_distance_along = self.mag * fraction_of_length;
-		let mut len_so_far = 0f64;
-		for LineSegmentMeasured {
-			a,
-			b,
-			mag: segment_length,
-		} in &self.segments
-		{
-			len_so_far += segment_length;
-			if len_so_far >= de_normalised_distance_along {
-				return (b - a).direction();
+	/// Returns the segments between two positions found by `position_at_distance`. `start` must not be after `end`.
+	fn segments_between(&self, start: (usize, f64, Vector2), end: (usize, f64, Vector2)) -> Vec<LineSegmentMeasured> {
+		let (start_index, start_distance, start_point) = start;
+		let (end_index, end_distance, end_point) = end;
+		if start_index == end_index {
+			return if end_distance > start_distance {
+				vec![LineSegmentMeasured { a: start_point, b: end_point, mag: end_distance - start_distance }]
+			} else {
+				Vec::new()
+			};
+		}
+		let mut result = Vec::with_capacity(end_index - start_index + 1);
+		let first = &self.segments[start_index];
+		if start_distance < first.mag {
+			result.push(LineSegmentMeasured { a: start_point, b: first.b, mag: first.mag - start_distance });
+		}
+		result.extend_from_slice(&self.segments[start_index + 1..end_index]);
+		if end_distance > 0f64 {
+			result.push(LineSegmentMeasured { a: self.segments[end_index].a, b: end_point, mag: end_distance });
+		}
+		result
+	}
+
+	/// Same as `interpolate` for many fractions of length at once. The results are in the same order as the fractions.
+	/// If the fractions are sorted the segments are walked only once, otherwise each fraction is found by binary search.
+	pub fn interpolate_many(&self, fractions_of_length: &[f64]) -> Vec<Option<Vector2>> {
+		let distances: Vec<f64> = fractions_of_length.iter().map(|fraction_of_length| self.mag * fraction_of_length).collect();
+		self.interpolate_many_at_distance(&distances)
+	}
+
+	/// Same as `interpolate_many`, but takes absolute distances along the linestring.
+	pub fn interpolate_many_at_distance(&self, distances_along: &[f64]) -> Vec<Option<Vector2>> {
+		if is_sorted(distances_along) {
+			match self.positions_at_sorted_distances(distances_along) {
+				Some(positions) => positions.into_iter().map(|(_, _, point)| Some(point)).collect(),
+				None => vec![None; distances_along.len()],
 			}
+		} else {
+			distances_along.iter().map(|&distance_along| self.interpolate_at_distance(distance_along)).collect()
 		}
-		return 0f64;
+	}
+
+	/// Same as `direction` for many fractions of length at once. The results are in the same order as the fractions.
+	/// If the fractions are sorted the segments are walked only once, otherwise each fraction is found by binary search.
+	pub fn direction_many(&self, fractions_of_length: &[f64]) -> Vec<f64> {
+		let distances: Vec<f64> = fractions_of_length.iter().map(|fraction_of_length| self.mag * fraction_of_length).collect();
+		self.direction_many_at_distance(&distances)
+	}
+
+	/// Same as `direction_many`, but takes absolute distances along the linestring.
+	pub fn direction_many_at_distance(&self, distances_along: &[f64]) -> Vec<f64> {
+		if is_sorted(distances_along) {
+			let mut index = 0;
+			distances_along
+				.iter()
+				.map(|&distance_along| {
+					while index < self.cumulative.len() && self.cumulative[index] < distance_along {
+						index += 1;
+					}
+					match self.segments.get(index) {
+						Some(LineSegmentMeasured { a, b, mag: _ }) => (b - a).direction(),
+						None => 0f64,
+					}
+				})
+				.collect()
+		} else {
+			distances_along.iter().map(|&distance_along| self.direction_at_distance(distance_along)).collect()
+		}
+	}
+
+	/// Same as `locate` for many points at once. The results are in the same order as the points.
+	pub fn locate_many(&self, points: &[Vector2]) -> Vec<Option<LocatedPoint>> {
+		points.iter().map(|&point| self.locate(point)).collect()
+	}
+
+	/// Converts a measure into a distance along the linestring, where the linestring is measured from `from_measure` to `to_measure`.
+	/// Measures may decrease along the linestring. Returns zero if `from_measure` and `to_measure` are equal.
+	pub fn distance_at_measure(&self, measure: f64, from_measure: f64, to_measure: f64) -> f64 {
+		if to_measure == from_measure {
+			return 0f64;
+		}
+		(measure - from_measure) / (to_measure - from_measure) * self.mag
 	}
 
 	pub fn offset_basic(&self, distance: f64) -> Option<Vec<Vector2>> {
-		if self.segments.len() == 0 {
+		self.offset_basic_with_options(distance, &OffsetOptions::default())
+	}
+
+	/// Like `offset_basic`, but the corners are joined as specified by `options`.
+	/// Returns `None` if the linestring has fewer than two points, or any point is not finite.
+	pub fn offset_basic_with_options(&self, distance: f64, options: &OffsetOptions) -> Option<Vec<Vector2>> {
+		if let Some(cleaned) = self.without_zero_length_segments() {
+			return cleaned.offset_basic_with_options(distance, options);
+		}
+		if self.segments.is_empty() || self.has_non_finite_points() {
+			return None;
+		}
+		let vertex_distances = vec![distance; self.segments.len() + 1];
+		Some(self.join_offset_segments(&self.offset_segments(distance), &vertex_distances, options))
+	}
+
+	/// Like `offset_segments` but the offset distance varies along the line.
+	/// `distance` is called with the fraction of length at each vertex, and the offset varies linearly between vertices.
+	pub fn offset_segments_variable<F: Fn(f64) -> f64>(&self, distance: F) -> Vec<LineSegmentMeasured> {
+		self.offset_segments_at_vertices(&self.vertex_distances(distance))
+	}
+
+	/// Like `offset_basic_with_options` but the offset distance varies along the line.
+	/// `distance` is called with the fraction of length at each vertex, and the offset varies linearly between vertices.
+	/// Returns `None` if the linestring has fewer than two points, or any point is not finite. Zero length segments are skipped.
+	pub fn offset_basic_variable<F: Fn(f64) -> f64>(&self, distance: F, options: &OffsetOptions) -> Option<Vec<Vector2>> {
+		if let Some(cleaned) = self.without_zero_length_segments() {
+			return cleaned.offset_basic_variable(distance, options);
+		}
+		if self.segments.is_empty() || self.has_non_finite_points() {
+			return None;
+		}
+		let vertex_distances = self.vertex_distances(distance);
+		let offset_segments = self.offset_segments_at_vertices(&vertex_distances);
+		Some(self.join_offset_segments(&offset_segments, &vertex_distances, options))
+	}
+
+	/// Like `offset_basic_variable` where the offset distance is linearly interpolated between `(fraction_of_length, distance)` breakpoints.
+	/// The breakpoints must be sorted by fraction of length. The offset is constant before the first and after the last breakpoint.
+	pub fn offset_basic_breakpoints(&self, breakpoints: &[(f64, f64)], options: &OffsetOptions) -> Option<Vec<Vector2>> {
+		let fractions: Vec<f64> = breakpoints.iter().map(|&(fraction, _)| fraction).collect();
+		self.with_vertices_at(&fractions)
+			.offset_basic_variable(|fraction_of_length| interpolate_breakpoints(breakpoints, fraction_of_length), options)
+	}
+
+	/// Returns a copy without any zero length segments, or `None` if there are none to remove.
+	/// Zero length segments have no direction, so they are dropped before offsetting. Segments with a NaN or infinite end are kept.
+	fn without_zero_length_segments(&self) -> Option<LineStringMeasured> {
+		if !self.segments.iter().any(|segment| segment.mag == 0f64) {
 			return None;
 		}
+		Some(LineStringMeasured::from_segments(self.segments.iter().filter(|segment| segment.mag != 0f64).cloned().collect()))
+	}
 
-		let offset_segments = self.offset_segments(distance);
+	/// True if any point has a NaN or infinite coordinate. Such linestrings can not be offset.
+	fn has_non_finite_points(&self) -> bool {
+		self.segments.iter().any(|segment| !segment.a.is_finite() || !segment.b.is_finite())
+	}
 
+	/// Joins consecutive offset segments. `vertex_distances` is the offset distance at each vertex of the original linestring.
+	/// If the linestring is closed, the last offset segment is also joined back to the first, and the result is closed.
+	fn join_offset_segments(&self, offset_segments: &[LineSegmentMeasured], vertex_distances: &[f64], options: &OffsetOptions) -> Vec<Vector2> {
 		let mut points = Vec::with_capacity(offset_segments.len() + 5);
-		points.push(offset_segments[0].a);
+		let closing_corner = if self.is_closed() {
+			let closing_corner = self.corner_points(
+				&offset_segments[offset_segments.len() - 1],
+				&offset_segments[0],
+				self.segments[0].a,
+				vertex_distances[0],
+				options,
+			);
+			points.push(closing_corner[closing_corner.len() - 1]);
+			Some(closing_corner)
+		} else {
+			points.push(offset_segments[0].a);
+			None
+		};
 
 		//for (mseg1, mseg2) in offset_segments.pairwise() {
-		for (mseg1, mseg2) in offset_segments.iter().zip(offset_segments[1..].iter()) {
-			let LineSegmentMeasured { a, b, mag: _ } = mseg1;
-			let LineSegmentMeasured { a: c, b: d, mag: _ } = mseg2;
-			let ab = b - a;
-			let cd = d - c;
-			if ab.cross(cd).abs() < 0.00000001 {
-				points.push(*b);
-			} else if let Some((intersection_point, time_ab, time_cd)) = mseg1.intersect(mseg2) {
-				let tip_ab = 0f64 <= time_ab && time_ab <= 1f64;
-				let fip_ab = !tip_ab;
-				let pfip_ab = fip_ab && time_ab > 0f64;
-				let tip_cd = 0f64 <= time_cd && time_cd <= 1f64;
-				let fip_cd = !tip_cd;
-
-				if tip_ab && tip_cd {
-					// Case 2a
-					// TODO: test for mitre limit
-					points.push(intersection_point);
-				} else if fip_ab && fip_cd {
-					// Case 2b.
-					if pfip_ab {
-						// TODO: test for mitre limit
-						points.push(intersection_point);
-					} else {
-						points.push(*b);
-						points.push(*c);
-					}
+		for (index, (mseg1, mseg2)) in offset_segments.iter().zip(offset_segments[1..].iter()).enumerate() {
+			points.extend(self.corner_points(mseg1, mseg2, self.segments[index].b, vertex_distances[index + 1], options));
+		}
+		match closing_corner {
+			Some(closing_corner) => points.extend(closing_corner),
+			None => points.push(offset_segments[offset_segments.len() - 1].b),
+		}
+		points
+	}
+
+	/// Returns the points which join two consecutive offset segments at the corner around `vertex`.
+	fn corner_points(&self, mseg1: &LineSegmentMeasured, mseg2: &LineSegmentMeasured, vertex: Vector2, distance: f64, options: &OffsetOptions) -> Vec<Vector2> {
+		let LineSegmentMeasured { a, b, mag: _ } = mseg1;
+		let LineSegmentMeasured { a: c, b: d, mag: _ } = mseg2;
+		let ab = b - a;
+		let cd = d - c;
+		if ab.cross(cd).abs() < 0.00000001 {
+			if ab.dot(cd) < 0f64 {
+				// The line doubles back on itself; an outside corner with no mitre point
+				options.join_points(vertex, *b, *c, None, distance, true)
+			} else {
+				vec![*b]
+			}
+		} else if let Some((intersection_point, time_ab, time_cd)) = mseg1.intersect(mseg2) {
+			let tip_ab = (0f64..=1f64).contains(&time_ab);
+			let fip_ab = !tip_ab;
+			let pfip_ab = fip_ab && time_ab > 0f64;
+			let tip_cd = (0f64..=1f64).contains(&time_cd);
+			let fip_cd = !tip_cd;
+
+			if tip_ab && tip_cd {
+				// Case 2a
+				// An inside corner. Anything but a mitre within the limit leaves a loop for `offset` to clip.
+				options.join_points(vertex, *b, *c, Some(intersection_point), distance, false)
+			} else if fip_ab && fip_cd {
+				// Case 2b.
+				if pfip_ab {
+					// An outside corner
+					options.join_points(vertex, *b, *c, Some(intersection_point), distance, true)
 				} else {
-					// Case 2c. (either ab or cd
-					points.push(*b);
-					points.push(*c);
+					vec![*b, *c]
+				}
+			} else {
+				// Case 2c. (either ab or cd
+				vec![*b, *c]
+			}
+		} else {
+			Vec::new()
+		}
+	}
+
+	fn offset_segments_at_vertices(&self, vertex_distances: &[f64]) -> Vec<LineSegmentMeasured> {
+		self.segments
+			.iter()
+			.zip(vertex_distances.iter().zip(vertex_distances.iter().skip(1)))
+			.map(|(LineSegmentMeasured { a, b, mag: _ }, (distance_a, distance_b))| {
+				let normal = (b - a).left().unit();
+				let offset_a = *a + normal * *distance_a;
+				let offset_b = *b + normal * *distance_b;
+				LineSegmentMeasured {
+					a: offset_a,
+					b: offset_b,
+					mag: offset_a.distance_to(offset_b),
+				}
+			})
+			.collect()
+	}
+
+	/// Returns a copy with extra vertices inserted at each of the sorted fractions of length, where there is not a vertex already.
+	fn with_vertices_at(&self, fractions_of_length: &[f64]) -> LineStringMeasured {
+		let mut points: Vec<Vector2> = Vec::with_capacity(self.segments.len() + fractions_of_length.len() + 1);
+		let mut fractions = fractions_of_length.iter().map(|fraction| fraction * self.mag).peekable();
+		let mut length_so_far = 0f64;
+		for LineSegmentMeasured { a, b, mag } in &self.segments {
+			points.push(*a);
+			while let Some(distance_along) = fractions.peek() {
+				let distance_remaining = distance_along - length_so_far;
+				if distance_remaining >= *mag {
+					break;
+				}
+				if distance_remaining > 0f64 {
+					points.push(*a + (b - a) * (distance_remaining / mag));
 				}
+				fractions.next();
 			}
+			length_so_far += mag;
 		}
-		points.push(offset_segments[offset_segments.len() - 1].b);
-		Some(points)
+		if let Some(segment) = self.segments.last() {
+			points.push(segment.b);
+		}
+		points.into()
 	}
 
-	// fn offset(&self, distance:f64) -> Option<LineString>{
-	// 	todo!();
-	// }
+	/// Evaluates `distance` at the fraction of length of each vertex
+	fn vertex_distances<F: Fn(f64) -> f64>(&self, distance: F) -> Vec<f64> {
+		let mut length_so_far = 0f64;
+		let mut result = Vec::with_capacity(self.segments.len() + 1);
+		result.push(distance(0f64));
+		for segment in &self.segments {
+			length_so_far += segment.mag;
+			result.push(distance(if self.mag > 0f64 { length_so_far / self.mag } else { 0f64 }));
+		}
+		result
+	}
+
+	/// Returns the polygon covering everything within `distance` of the linestring, with the ends closed as specified by `cap`.
+	/// The polygon is returned as closed rings; the anticlockwise exterior comes first, followed by any clockwise holes.
+	/// Returns `None` if the linestring has fewer than two points, or any point is not finite.
+	pub fn buffer(&self, distance: f64, cap: CapStyle, options: &OffsetOptions) -> Option<Vec<Vec<Vector2>>> {
+		if let Some(cleaned) = self.without_zero_length_segments() {
+			return cleaned.buffer(distance, cap, options);
+		}
+		if self.has_non_finite_points() {
+			return None;
+		}
+		let radius = distance.abs();
+		let first = self.segments.first()?;
+		let last = self.segments.last()?;
+		let start_normal = (first.b - first.a).left().unit() * radius;
+		let end_normal = (last.b - last.a).left().unit() * radius;
+		if radius == 0f64 {
+			return Some(Vec::new());
+		}
+		if self.is_closed() {
+			// A ring has no ends to cap; the buffer is the region between the offsets to either side
+			let mut right = self.offset_basic_with_options(-radius, options)?;
+			let mut left = self.offset_basic_with_options(radius, options)?;
+			right.pop();
+			left.pop();
+			left.reverse();
+			return Some(polyline::positive_rings(&[right, left], radius * 0.000001, polyline::keep_all));
+		}
+
+		let mut raw_ring = self.offset_basic_with_options(-radius, options)?;
+		match cap {
+			CapStyle::Flat => {}
+			CapStyle::Square => {
+				raw_ring.push(last.b - end_normal - end_normal.left());
+				raw_ring.push(last.b + end_normal - end_normal.left());
+			}
+			CapStyle::Round(tolerance) => raw_ring.extend(tolerance.arc(last.b, -end_normal, std::f64::consts::PI)),
+		}
+		raw_ring.extend(self.offset_basic_with_options(radius, options)?.into_iter().rev());
+		match cap {
+			CapStyle::Flat => {}
+			CapStyle::Square => {
+				raw_ring.push(first.a + start_normal + start_normal.left());
+				raw_ring.push(first.a - start_normal + start_normal.left());
+			}
+			CapStyle::Round(tolerance) => raw_ring.extend(tolerance.arc(first.a, start_normal, std::f64::consts::PI)),
+		}
+		Some(polyline::positive_rings(&[raw_ring], radius * 0.000001, polyline::keep_all))
+	}
+
+	/// Returns the polygon between the linestring and its offset; on the left side for positive `distance`
+	/// or on the right side for negative `distance`.
+	/// The polygon is returned as closed rings; the anticlockwise exterior comes first, followed by any clockwise holes.
+	/// Returns `None` if the linestring has fewer than two points, or any point is not finite.
+	pub fn buffer_one_sided(&self, distance: f64, options: &OffsetOptions) -> Option<Vec<Vec<Vector2>>> {
+		if let Some(cleaned) = self.without_zero_length_segments() {
+			return cleaned.buffer_one_sided(distance, options);
+		}
+		let offset = self.offset_basic_with_options(distance, options)?;
+		if distance == 0f64 {
+			return Some(Vec::new());
+		}
+		let original: Vec<Vector2> = self.into();
+		let (mut forward, mut backward) = if distance > 0f64 { (original, offset) } else { (offset, original) };
+		backward.reverse();
+		if self.is_closed() {
+			// The ring and its offset are separate rings
+			forward.pop();
+			backward.pop();
+			return Some(polyline::positive_rings(&[forward, backward], distance.abs() * 0.000001, polyline::keep_all));
+		}
+		forward.extend(backward);
+		Some(polyline::positive_rings(&[forward], distance.abs() * 0.000001, polyline::keep_all))
+	}
+
+	/// Offsets the linestring using the full algorithm described by Liu et al.:
+	///
+	/// 1. The raw offset is computed using `offset_basic`,
+	/// 2. Dual clipping: the raw offset is split wherever it crosses itself, the original linestring,
+	///    or the boundary of the region within `distance` of the original linestring,
+	/// 3. Pieces of the raw offset which are closer than `distance` to the original linestring are discarded,
+	/// 4. The remaining pieces are joined where they touch end to end.
+	///
+	/// Distance is measured perpendicular to each segment, or into the join at each corner,
+	/// so the ends of the result are flat rather than rounded.
+	/// Returns `None` if the linestring has fewer than two points, or any point is not finite.
+	pub fn offset(&self, distance: f64) -> Option<Vec<LineStringMeasured>> {
+		self.offset_with_options(distance, &OffsetOptions::default())
+	}
+
+	/// Like `offset`, but the corners which turn away from the offset side are joined as specified by `options`.
+	/// Inside corners are always clipped where the offset crosses itself.
+	pub fn offset_with_options(&self, distance: f64, options: &OffsetOptions) -> Option<Vec<LineStringMeasured>> {
+		if let Some(cleaned) = self.without_zero_length_segments() {
+			return cleaned.offset_with_options(distance, options);
+		}
+		let raw_offset = self.offset_basic_with_options(distance, options)?;
+		if distance == 0f64 {
+			return Some(vec![self.clone()]);
+		}
+		let radius = distance.abs();
+		let tolerance = radius * 0.000001;
+
+		let mut boundaries = self.clipping_boundaries(radius);
+		boundaries.extend(polyline::segments(&self.offset_basic_with_options(-distance, options)?));
+		let mut join_regions = self.join_regions(distance, options);
+		join_regions.extend(self.join_regions(-distance, options));
+
+		let raw_segments = polyline::segments(&raw_offset);
+		let mut cuts = vec![Vec::new(); raw_segments.len()];
+		polyline::add_self_intersection_cuts(&raw_segments, &mut cuts);
+		polyline::add_intersection_cuts(&raw_segments, &boundaries, &mut cuts);
+
+		// crossings at the same place can land a rounding error apart; drop the crumbs of offset left between them
+		let pieces: Vec<Vec<Vector2>> = polyline::split_at_cuts(&raw_offset, cuts)
+			.into_iter()
+			.filter(|piece| polyline::length(piece) > tolerance)
+			.collect();
+		let midpoints: Vec<Vector2> = pieces.iter().map(|piece| polyline::midpoint(piece)).collect();
+		let within_band = polyline::points_within_offset_band(&self.segments, &midpoints, radius - tolerance);
+		let within_join = polyline::points_in_convex_polygons(&join_regions, &midpoints, tolerance);
+		let valid_pieces = pieces
+			.into_iter()
+			.zip(within_band.into_iter().zip(within_join))
+			.filter(|(_, (within_band, within_join))| !within_band && !within_join)
+			.map(|(piece, _)| piece)
+			.collect();
+
+		let mut parts = polyline::join_pieces(valid_pieces, tolerance);
+		if self.is_closed() && parts.len() > 1 && parts[parts.len() - 1][parts[parts.len() - 1].len() - 1].distance_to(parts[0][0]) <= tolerance {
+			// the offset of a ring may have been split where the ring starts and ends
+			let last_part = parts.pop().unwrap();
+			parts = polyline::join_pieces(std::iter::once(last_part).chain(parts).collect(), tolerance);
+		}
+		Some(parts.into_iter().map(LineStringMeasured::from).collect())
+	}
+
+	/// Offsets a closed ring outwards for positive `distance` or inwards for negative `distance`, regardless of the ring's orientation.
+	/// The ring is closed first if it is not closed already.
+	///
+	/// The result is a list of polygons, since an inward offset may split the ring into several parts, or collapse it entirely.
+	/// Each polygon is a list of closed rings; an anticlockwise exterior followed by any clockwise holes.
+	pub fn offset_ring(&self, distance: f64, options: &OffsetOptions) -> Vec<Vec<Vec<Vector2>>> {
+		LineStringMeasured::offset_polygon(std::slice::from_ref(self), distance, options)
+	}
+
+	/// Offsets a polygon outwards for positive `distance` or inwards for negative `distance`.
+	/// The first ring is the exterior and the rest are holes. Rings may have either orientation, and are closed first if they are not closed already.
+	///
+	/// The result is a list of polygons, since an inward offset may split the polygon into several parts, or collapse it entirely.
+	/// Each polygon is a list of closed rings; an anticlockwise exterior followed by any clockwise holes.
+	pub fn offset_polygon(rings: &[LineStringMeasured], distance: f64, options: &OffsetOptions) -> Vec<Vec<Vec<Vector2>>> {
+		let oriented_rings: Vec<LineStringMeasured> = rings
+			.iter()
+			.enumerate()
+			.filter(|(_, ring)| ring.segments.len() > 1)
+			.map(|(index, ring)| {
+				let ring = ring.closed();
+				let mut points: Vec<Vector2> = (&ring).into();
+				// the exterior is offset anticlockwise and the holes clockwise, so that the outside is always on the right
+				let is_anticlockwise = polyline::signed_area(&points) > 0f64;
+				if is_anticlockwise == (index == 0) {
+					ring
+				} else {
+					points.reverse();
+					points.into()
+				}
+			})
+			.collect();
+		let raw_rings: Vec<Vec<Vector2>> = oriented_rings
+			.iter()
+			.filter_map(|ring| {
+				let mut raw_ring = ring.offset_basic_with_options(-distance, options)?;
+				raw_ring.pop();
+				Some(raw_ring)
+			})
+			.collect();
+
+		// Where an inward offset collapses completely the raw offset can turn inside out without changing orientation,
+		// so anything too close to the original rings is discarded as well
+		let radius = distance.abs();
+		let tolerance = radius.max(1f64) * 0.000001;
+		let join_regions: Vec<Vec<Vector2>> = oriented_rings
+			.iter()
+			.flat_map(|ring| {
+				let mut regions = ring.join_regions(radius, options);
+				regions.extend(ring.join_regions(-radius, options));
+				regions
+			})
+			.collect();
+		let original_segments: Vec<LineSegmentMeasured> = oriented_rings.iter().flat_map(|ring| ring.segments.iter().cloned()).collect();
+		let is_far_enough = |points: &[Vector2]| -> Vec<bool> {
+			let within_band = polyline::points_within_offset_band(&original_segments, points, radius - tolerance);
+			let within_join = polyline::points_in_convex_polygons(&join_regions, points, tolerance);
+			within_band.into_iter().zip(within_join).map(|(within_band, within_join)| !within_band && !within_join).collect()
+		};
+		polyline::group_polygons(polyline::positive_rings(&raw_rings, tolerance, is_far_enough))
+	}
+
+	/// The original segments, their untrimmed offsets to either side, and the perpendicular ends of each offset band.
+	fn clipping_boundaries(&self, radius: f64) -> Vec<LineSegmentMeasured> {
+		let mut boundaries = Vec::with_capacity(self.segments.len() * 5);
+		for segment in self.segments.iter().filter(|segment| segment.mag > 0f64) {
+			let normal = (segment.b - segment.a).left().unit() * radius;
+			for &(a, b) in &[
+				(segment.a, segment.b),
+				(segment.a + normal, segment.b + normal),
+				(segment.a - normal, segment.b - normal),
+				(segment.a - normal, segment.a + normal),
+				(segment.b - normal, segment.b + normal),
+			] {
+				boundaries.push(LineSegmentMeasured { a, b, mag: a.distance_to(b) });
+			}
+		}
+		boundaries
+	}
+
+	/// The region filled by the join at each corner which turns away from the offset side.
+	/// Each region is convex, and bounded by the original vertex, the two offset segment ends, and the join between them.
+	fn join_regions(&self, distance: f64, options: &OffsetOptions) -> Vec<Vec<Vector2>> {
+		let segments: Vec<&LineSegmentMeasured> = self.segments.iter().filter(|segment| segment.mag > 0f64).collect();
+		let offset_segments: Vec<LineSegmentMeasured> = segments
+			.iter()
+			.map(|segment| {
+				let offset_vector = (segment.b - segment.a).left().unit() * distance;
+				LineSegmentMeasured { a: segment.a + offset_vector, b: segment.b + offset_vector, mag: segment.mag }
+			})
+			.collect();
+		let mut corners: Vec<(usize, usize)> = (1..segments.len()).map(|index| (index - 1, index)).collect();
+		if self.is_closed() && segments.len() > 1 {
+			corners.push((segments.len() - 1, 0));
+		}
+		let mut regions = Vec::new();
+		for (index, next_index) in corners {
+			let segment = segments[index];
+			let next_segment = segments[next_index];
+			let ab = segment.b - segment.a;
+			let cd = next_segment.b - next_segment.a;
+			let turn = ab.cross(cd);
+			if turn * distance > 0f64 || (turn == 0f64 && ab.dot(cd) >= 0f64) {
+				continue;
+			}
+			let b = offset_segments[index].b;
+			let c = offset_segments[next_index].a;
+			let mitre_point = offset_segments[index].intersect(&offset_segments[next_index]).map(|(point, _, _)| point);
+			let mut region = vec![segment.b, b];
+			region.extend(options.join_points(segment.b, b, c, mitre_point, distance, true));
+			region.push(c);
+			region.dedup();
+			regions.push(region);
+		}
+		regions
+	}
+}
+
+/// `Result` returning versions of the methods above, which report why they failed instead of returning `None` or a default.
+impl LineStringMeasured {
+	/// Checks that the linestring has at least one segment, finite coordinates and some length.
+	pub fn check(&self) -> Result<(), LineStringError> {
+		if self.segments.is_empty() {
+			return Err(LineStringError::EmptyLine);
+		}
+		if self.segments.iter().any(|segment| !segment.a.is_finite() || !segment.b.is_finite()) || !self.mag.is_finite() {
+			return Err(LineStringError::NonFinite);
+		}
+		if self.mag <= 0f64 {
+			return Err(LineStringError::ZeroLength);
+		}
+		Ok(())
+	}
+
+	/// Reports non-finite points, duplicate points and spikes. Points closer than `tolerance` to the point before them count as duplicates.
+	/// Vertices where the angle between the segments before and after is at most `spike_angle` radians count as spikes;
+	/// with a `spike_angle` of zero only exact reversals are reported.
+	pub fn validate(&self, tolerance: f64, spike_angle: f64) -> ValidationReport {
+		let points: Vec<Vector2> = self.into();
+		let mut report = ValidationReport::default();
+		let mut distinct: Vec<(usize, Vector2)> = Vec::with_capacity(points.len());
+		for (index, &point) in points.iter().enumerate() {
+			if !point.is_finite() {
+				report.non_finite.push(index);
+			} else if matches!(distinct.last(), Some(&(_, previous)) if previous.distance_to(point) <= tolerance) {
+				report.duplicates.push(index);
+			} else {
+				distinct.push((index, point));
+			}
+		}
+		for window in distinct.windows(3) {
+			let (_, before) = window[0];
+			let (index, vertex) = window[1];
+			let (_, after) = window[2];
+			let incoming = vertex - before;
+			let outgoing = after - vertex;
+			// zero when the line turns straight back on itself
+			let angle = incoming.cross(outgoing).abs().atan2(-incoming.dot(outgoing));
+			if angle <= spike_angle.max(f64::EPSILON * 16f64) {
+				report.spikes.push(index);
+			}
+		}
+		report.too_few_points = distinct.len() < 2;
+		report
+	}
+
+	/// Returns a copy with duplicate points (closer than `tolerance` to the point before) removed, so there are no zero length segments.
+	/// Fails if any coordinate is not finite, or if fewer than two distinct points remain. Spikes are kept.
+	pub fn clean(&self, tolerance: f64) -> Result<LineStringMeasured, LineStringError> {
+		let points: Vec<Vector2> = self.into();
+		if points.iter().any(|point| !point.is_finite()) {
+			return Err(LineStringError::NonFinite);
+		}
+		let mut cleaned: Vec<Vector2> = Vec::with_capacity(points.len());
+		for point in points {
+			let is_distinct = match cleaned.last() {
+				None => true,
+				Some(previous) => previous.distance_to(point) > tolerance,
+			};
+			if is_distinct {
+				cleaned.push(point);
+			}
+		}
+		if cleaned.len() < 2 {
+			return Err(LineStringError::EmptyLine);
+		}
+		Ok(cleaned.into())
+	}
+
+	/// Checks the linestring, and that the fraction of length is finite and within `0..=1`.
+	fn check_fraction(&self, fraction_of_length: f64) -> Result<(), LineStringError> {
+		self.check()?;
+		check_range(fraction_of_length, 0f64, 1f64)
+	}
+
+	/// Checks the linestring, and that the distance is finite and within `0..=self.mag`.
+	fn check_distance(&self, distance_along: f64) -> Result<(), LineStringError> {
+		self.check()?;
+		check_range(distance_along, 0f64, self.mag)
+	}
+
+	pub fn try_cut(&self, fraction_of_length: f64) -> Result<(Option<LineStringMeasured>, Option<LineStringMeasured>), LineStringError> {
+		self.check_fraction(fraction_of_length)?;
+		Ok(self.cut(fraction_of_length))
+	}
+
+	pub fn try_cut_at_distance(&self, distance_along: f64) -> Result<(Option<LineStringMeasured>, Option<LineStringMeasured>), LineStringError> {
+		self.check_distance(distance_along)?;
+		Ok(self.cut_at_distance(distance_along))
+	}
+
+	pub fn try_substring(&self, fraction_of_length_start: f64, fraction_of_length_end: f64) -> Result<LineStringMeasured, LineStringError> {
+		self.check_fraction(fraction_of_length_start)?;
+		self.check_fraction(fraction_of_length_end)?;
+		self.substring(fraction_of_length_start, fraction_of_length_end).ok_or(LineStringError::EmptyLine)
+	}
+
+	pub fn try_interpolate(&self, fraction_of_length: f64) -> Result<Vector2, LineStringError> {
+		self.check_fraction(fraction_of_length)?;
+		self.interpolate(fraction_of_length).ok_or(LineStringError::EmptyLine)
+	}
+
+	pub fn try_interpolate_at_distance(&self, distance_along: f64) -> Result<Vector2, LineStringError> {
+		self.check_distance(distance_along)?;
+		self.interpolate_at_distance(distance_along).ok_or(LineStringError::EmptyLine)
+	}
+
+	/// Fails with `DegenerateSegment` if the position falls on a segment of zero length.
+	pub fn try_direction(&self, fraction_of_length: f64) -> Result<f64, LineStringError> {
+		self.check_fraction(fraction_of_length)?;
+		self.try_direction_at_distance(self.mag * fraction_of_length)
+	}
+
+	/// Fails with `DegenerateSegment` if the position falls on a segment of zero length.
+	pub fn try_direction_at_distance(&self, distance_along: f64) -> Result<f64, LineStringError> {
+		self.check_distance(distance_along)?;
+		let index = self
+			.cumulative
+			.partition_point(|&distance_to_end| distance_to_end < distance_along)
+			.min(self.segments.len() - 1);
+		let LineSegmentMeasured { a, b, mag } = &self.segments[index];
+		if *mag <= 0f64 {
+			return Err(LineStringError::DegenerateSegment { index });
+		}
+		Ok((b - a).direction())
+	}
+
+	pub fn try_locate(&self, point: Vector2) -> Result<LocatedPoint, LineStringError> {
+		if !point.is_finite() {
+			return Err(LineStringError::NonFinite);
+		}
+		self.check()?;
+		self.locate(point).ok_or(LineStringError::EmptyLine)
+	}
+
+	/// Fails with `DegenerateSegment` if any segment has zero length, since it has no direction to offset from.
+	pub fn try_offset_basic(&self, distance: f64, options: &OffsetOptions) -> Result<Vec<Vector2>, LineStringError> {
+		if !distance.is_finite() {
+			return Err(LineStringError::NonFinite);
+		}
+		self.check_offsettable()?;
+		self.offset_basic_with_options(distance, options).ok_or(LineStringError::EmptyLine)
+	}
+
+	/// Fails with `DegenerateSegment` if any segment has zero length, since it has no direction to offset from.
+	pub fn try_offset(&self, distance: f64, options: &OffsetOptions) -> Result<Vec<LineStringMeasured>, LineStringError> {
+		if !distance.is_finite() {
+			return Err(LineStringError::NonFinite);
+		}
+		self.check_offsettable()?;
+		self.offset_with_options(distance, options).ok_or(LineStringError::EmptyLine)
+	}
+
+	fn check_offsettable(&self) -> Result<(), LineStringError> {
+		self.check()?;
+		match self.segments.iter().position(|segment| segment.mag <= 0f64) {
+			Some(index) => Err(LineStringError::DegenerateSegment { index }),
+			None => Ok(()),
+		}
+	}
+}
+
+fn check_range(value: f64, min: f64, max: f64) -> Result<(), LineStringError> {
+	if !value.is_finite() {
+		Err(LineStringError::NonFinite)
+	} else if value < min || value > max {
+		Err(LineStringError::OutOfRange { value, min, max })
+	} else {
+		Ok(())
+	}
+}
+
+fn is_sorted(values: &[f64]) -> bool {
+	values.iter().zip(values.iter().skip(1)).all(|(a, b)| a <= b)
 }
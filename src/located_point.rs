@@ -0,0 +1,39 @@
+use crate::vector2::Vector2;
+
+/// Which side of a linestring a point is on, looking along the direction of the linestring.
+/// `Left` is the same side as `Vector2::left` and a positive offset distance.
+/// A point beyond an end of the linestring and exactly in line with its end segment is counted as `Left`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+	Left,
+	Right,
+	On,
+}
+
+/// The result of projecting a point onto a linestring.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocatedPoint {
+	/// The closest point on the linestring
+	pub point: Vector2,
+	pub fraction_of_length: f64,
+	pub distance_along: f64,
+	/// The straight line distance from the original point to `point`.
+	/// Beyond the ends of the linestring and around the outside of corners this is more than `offset`.
+	pub distance_from_line: f64,
+	/// The perpendicular distance from the nearest segment, extended past its ends, positive on the left.
+	/// This is the offset distance which would pass through the original point.
+	pub offset: f64,
+	/// `On` if the original point is on the linestring, to within a tiny fraction of its length
+	pub side: Side,
+}
+
+impl LocatedPoint {
+	/// `distance_from_line`, positive on the left and negative on the right.
+	/// It is the same as `offset` unless the closest point is an end of the linestring or the outside of a corner.
+	pub fn signed_distance_from_line(&self) -> f64 {
+		match self.side {
+			Side::Right => -self.distance_from_line,
+			_ => self.distance_from_line,
+		}
+	}
+}
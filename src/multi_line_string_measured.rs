@@ -0,0 +1,206 @@
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::located_point::LocatedPoint;
+use crate::offset_options::OffsetOptions;
+
+/// Whether the straight line distance between the end of one part and the start of the next counts towards the distance along a `MultiLineStringMeasured`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gaps {
+	Counted,
+	NotCounted,
+}
+
+/// Several linestrings measured one after the other as a single route.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiLineStringMeasured {
+	pub(crate) parts: Vec<LineStringMeasured>,
+	pub(crate) gaps: Gaps,
+	/// The distance along the route to the start of each part
+	pub(crate) part_starts: Vec<f64>,
+	pub(crate) mag: f64,
+}
+
+impl MultiLineStringMeasured {
+	/// Parts with no segments are discarded.
+	pub fn new(parts: Vec<LineStringMeasured>, gaps: Gaps) -> MultiLineStringMeasured {
+		let parts: Vec<LineStringMeasured> = parts.into_iter().filter(|part| !part.segments.is_empty()).collect();
+		let mut part_starts = Vec::with_capacity(parts.len());
+		let mut distance_so_far = 0f64;
+		for (index, part) in parts.iter().enumerate() {
+			part_starts.push(distance_so_far);
+			distance_so_far += part.mag;
+			if let (Gaps::Counted, Some(next)) = (gaps, parts.get(index + 1)) {
+				let (gap_start, gap_end) = gap_ends(part, next);
+				distance_so_far += gap_start.distance_to(gap_end);
+			}
+		}
+		MultiLineStringMeasured {
+			parts,
+			gaps,
+			part_starts,
+			mag: distance_so_far,
+		}
+	}
+
+	pub fn parts(&self) -> &[LineStringMeasured] {
+		&self.parts
+	}
+
+	pub fn gaps(&self) -> Gaps {
+		self.gaps
+	}
+
+	/// The distance along the route to the start of each part.
+	pub fn part_starts(&self) -> &[f64] {
+		&self.part_starts
+	}
+
+	pub fn magnitude(&self) -> f64 {
+		self.mag
+	}
+
+	pub fn interpolate(&self, fraction_of_length: f64) -> Option<Vector2> {
+		self.interpolate_at_distance(self.mag * fraction_of_length)
+	}
+
+	/// Gets the point some distance along the route. Distances in a counted gap are interpolated along a straight line across the gap.
+	pub fn interpolate_at_distance(&self, distance_along: f64) -> Option<Vector2> {
+		let index = self.part_at_distance(distance_along)?;
+		let part = &self.parts[index];
+		let distance_along_part = distance_along - self.part_starts[index];
+		match self.parts.get(index + 1) {
+			Some(next) if distance_along_part > part.mag => {
+				let (gap_start, gap_end) = gap_ends(part, next);
+				let gap_length = self.part_starts[index + 1] - self.part_starts[index] - part.mag;
+				if gap_length > 0f64 {
+					Some(gap_start + (gap_end - gap_start) * ((distance_along_part - part.mag) / gap_length).min(1f64))
+				} else {
+					Some(gap_start)
+				}
+			}
+			_ => part.interpolate_at_distance(distance_along_part),
+		}
+	}
+
+	/// Finds the closest point on any part. The distance and fraction returned are along the whole route.
+	/// Parts which give a NaN distance, because of non-finite coordinates, are skipped.
+	pub fn locate(&self, point: Vector2) -> Option<LocatedPoint> {
+		self.parts
+			.iter()
+			.zip(self.part_starts.iter())
+			.filter_map(|(part, part_start)| {
+				part.locate(point).map(|located| LocatedPoint {
+					distance_along: part_start + located.distance_along,
+					fraction_of_length: if self.mag > 0f64 { (part_start + located.distance_along) / self.mag } else { 0f64 },
+					..located
+				})
+			})
+			.filter(|located| !located.distance_from_line.is_nan())
+			.min_by(|a, b| a.distance_from_line.total_cmp(&b.distance_from_line))
+	}
+
+	pub fn cut(&self, fraction_of_length: f64) -> (Option<MultiLineStringMeasured>, Option<MultiLineStringMeasured>) {
+		self.cut_at_distance(self.mag * fraction_of_length)
+	}
+
+	/// Splits the route at a distance along it. Parts and gaps are kept as they are on either side of the cut.
+	/// A cut inside a counted gap splits the gap between the two sides, so their lengths still add up to the whole.
+	pub fn cut_at_distance(&self, distance_along: f64) -> (Option<MultiLineStringMeasured>, Option<MultiLineStringMeasured>) {
+		if self.parts.is_empty() {
+			(None, None)
+		} else if distance_along <= 0f64 {
+			(None, Some(self.clone()))
+		} else if distance_along >= self.mag {
+			(Some(self.clone()), None)
+		} else {
+			(self.substring_at_distance(0f64, distance_along), self.substring_at_distance(distance_along, self.mag))
+		}
+	}
+
+	pub fn substring(&self, fraction_of_length_start: f64, fraction_of_length_end: f64) -> Option<MultiLineStringMeasured> {
+		self.substring_at_distance(self.mag * fraction_of_length_start, self.mag * fraction_of_length_end)
+	}
+
+	/// Returns the parts of the route between two distances along it, which are clamped to the route.
+	/// If the start is after the end the result runs backwards. If they are equal the result is a single zero length part at that point.
+	/// If the start or end is inside a counted gap, a zero length part is added at that point so that the rest of the gap is still counted.
+	/// Returns `None` if there are no parts, or if the whole range is inside a gap.
+	pub fn substring_at_distance(&self, distance_along_start: f64, distance_along_end: f64) -> Option<MultiLineStringMeasured> {
+		let from = distance_along_start.max(0f64).min(self.mag);
+		let to = distance_along_end.max(0f64).min(self.mag);
+		if from > to {
+			return self.substring_at_distance(to, from).map(|result| result.reversed());
+		}
+		let parts: Vec<LineStringMeasured> = if from == to {
+			let index = self.part_at_distance(from)?;
+			let distance_along_part = from - self.part_starts[index];
+			if distance_along_part > self.parts[index].mag {
+				return None;
+			}
+			vec![self.parts[index].substring_at_distance(distance_along_part, distance_along_part)?]
+		} else {
+			let mut parts: Vec<LineStringMeasured> = self
+				.parts
+				.iter()
+				.zip(self.part_starts.iter())
+				.filter(|(part, &part_start)| from < part_start + part.mag && part_start < to)
+				.filter_map(|(part, &part_start)| part.substring_at_distance(from - part_start, to - part_start))
+				.collect();
+			if !parts.is_empty() {
+				if let Some(start) = self.point_in_gap(from) {
+					parts.insert(0, vec![start, start].into());
+				}
+				if let Some(end) = self.point_in_gap(to) {
+					parts.push(vec![end, end].into());
+				}
+			}
+			parts
+		};
+		if parts.is_empty() {
+			None
+		} else {
+			Some(MultiLineStringMeasured::new(parts, self.gaps))
+		}
+	}
+
+	/// Returns a copy of the route running in the opposite direction.
+	pub fn reversed(&self) -> MultiLineStringMeasured {
+		MultiLineStringMeasured::new(self.parts.iter().rev().map(|part| part.reversed()).collect(), self.gaps)
+	}
+
+	/// Offsets each part, returning the valid pieces of every part in order. See `LineStringMeasured::offset`.
+	pub fn offset(&self, distance: f64) -> Vec<LineStringMeasured> {
+		self.offset_with_options(distance, &OffsetOptions::default())
+	}
+
+	pub fn offset_with_options(&self, distance: f64, options: &OffsetOptions) -> Vec<LineStringMeasured> {
+		self.parts
+			.iter()
+			.filter_map(|part| part.offset_with_options(distance, options))
+			.flatten()
+			.collect()
+	}
+
+	/// The point at a distance along the route, if that distance is inside a counted gap rather than on a part.
+	fn point_in_gap(&self, distance_along: f64) -> Option<Vector2> {
+		let index = self.part_at_distance(distance_along)?;
+		if distance_along - self.part_starts[index] > self.parts[index].mag {
+			self.interpolate_at_distance(distance_along)
+		} else {
+			None
+		}
+	}
+
+	/// The index of the part which starts at or before the distance, clamped to the first part.
+	fn part_at_distance(&self, distance_along: f64) -> Option<usize> {
+		if self.parts.is_empty() {
+			return None;
+		}
+		Some(self.part_starts.partition_point(|&part_start| part_start <= distance_along).max(1) - 1)
+	}
+}
+
+/// The end of one part and the start of the next.
+fn gap_ends(part: &LineStringMeasured, next: &LineStringMeasured) -> (Vector2, Vector2) {
+	(part.segments[part.segments.len() - 1].b, next.segments[0].a)
+}
@@ -0,0 +1,322 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::located_point::LocatedPoint;
+
+/// A graph of linestrings which share end points. Edges can be travelled in either direction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network {
+	pub nodes: Vec<Vector2>,
+	pub edges: Vec<Edge>,
+	/// End points closer than this are snapped to the same node
+	pub tolerance: f64,
+	/// For each node, the edges which touch it and the node at the other end
+	adjacency: Vec<Vec<(usize, usize)>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+	pub line: LineStringMeasured,
+	pub from_node: usize,
+	pub to_node: usize,
+}
+
+/// A point located on one edge of a network.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkLocation {
+	pub edge: usize,
+	pub located: LocatedPoint,
+}
+
+/// The part of an edge travelled by a path. `start_fraction` is greater than `end_fraction` if the edge is travelled backwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathEdge {
+	pub edge: usize,
+	pub start_fraction: f64,
+	pub end_fraction: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkPath {
+	/// The path stitched into one linestring
+	pub line: LineStringMeasured,
+	/// The edges travelled, in order
+	pub edges: Vec<PathEdge>,
+	pub length: f64,
+}
+
+impl Network {
+	/// Builds a network, snapping each end point to the first node within `tolerance`. Lines with no segments are discarded.
+	pub fn new(lines: Vec<LineStringMeasured>, tolerance: f64) -> Network {
+		let mut nodes: Vec<Vector2> = Vec::new();
+		// nodes bucketed into square cells as wide as the tolerance, so only the neighbouring cells need to be searched
+		let cell_size = if tolerance > 0f64 { tolerance } else { 1f64 };
+		let cell = |point: Vector2| ((point.x / cell_size).floor() as i64, (point.y / cell_size).floor() as i64);
+		let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+		let mut snap = |point: Vector2| -> usize {
+			let (column, row) = cell(point);
+			let nearby = (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (column.saturating_add(dx), row.saturating_add(dy))));
+			let found = nearby
+				.filter_map(|key| grid.get(&key))
+				.flatten()
+				.copied()
+				.filter(|&index| nodes[index].distance_to(point) <= tolerance)
+				.min();
+			match found {
+				Some(index) => index,
+				None => {
+					nodes.push(point);
+					grid.entry((column, row)).or_default().push(nodes.len() - 1);
+					nodes.len() - 1
+				}
+			}
+		};
+		let edges: Vec<Edge> = lines
+			.into_iter()
+			.filter(|line| !line.segments.is_empty())
+			.map(|line| {
+				let from_node = snap(line.segments[0].a);
+				let to_node = snap(line.segments[line.segments.len() - 1].b);
+				Edge { line, from_node, to_node }
+			})
+			.collect();
+		let mut adjacency = vec![Vec::new(); nodes.len()];
+		for (index, edge) in edges.iter().enumerate() {
+			adjacency[edge.from_node].push((index, edge.to_node));
+			adjacency[edge.to_node].push((index, edge.from_node));
+		}
+		Network { nodes, edges, tolerance, adjacency }
+	}
+
+	/// Finds the closest point on any edge. Edges which give a NaN distance, because of non-finite coordinates, are skipped.
+	pub fn locate(&self, point: Vector2) -> Option<NetworkLocation> {
+		self.edges
+			.iter()
+			.enumerate()
+			.filter_map(|(edge, Edge { line, .. })| line.locate(point).map(|located| NetworkLocation { edge, located }))
+			.filter(|location| !location.located.distance_from_line.is_nan())
+			.min_by(|a, b| a.located.distance_from_line.total_cmp(&b.located.distance_from_line))
+	}
+
+	/// Locates both points on the network, then finds the shortest path between them.
+	pub fn shortest_path(&self, from: Vector2, to: Vector2) -> Option<NetworkPath> {
+		self.shortest_path_between(&self.locate(from)?, &self.locate(to)?)
+	}
+
+	/// Finds the shortest path between two locations using Dijkstra's algorithm. Returns `None` if they are not connected.
+	pub fn shortest_path_between(&self, from: &NetworkLocation, to: &NetworkLocation) -> Option<NetworkPath> {
+		let start_edge = &self.edges[from.edge];
+		let start_distance = from.located.distance_along;
+		let end_edge = &self.edges[to.edge];
+		let end_distance = to.located.distance_along;
+
+		let mut distances = vec![f64::INFINITY; self.nodes.len()];
+		let mut previous: Vec<Option<Step>> = vec![None; self.nodes.len()];
+		let mut queue = BinaryHeap::new();
+		for &(node, distance, towards_end) in &[
+			(start_edge.from_node, start_distance, false),
+			(start_edge.to_node, start_edge.line.mag - start_distance, true),
+		] {
+			if distance < distances[node] {
+				distances[node] = distance;
+				previous[node] = Some(Step::Start { towards_end });
+				queue.push(Visit { distance, node });
+			}
+		}
+		while let Some(Visit { distance, node }) = queue.pop() {
+			if distance > distances[node] {
+				continue;
+			}
+			for &(edge, neighbour) in &self.adjacency[node] {
+				let next_distance = distance + self.edges[edge].line.mag;
+				if next_distance < distances[neighbour] {
+					distances[neighbour] = next_distance;
+					previous[neighbour] = Some(Step::Edge { edge, from_node: node });
+					queue.push(Visit { distance: next_distance, node: neighbour });
+				}
+			}
+		}
+
+		// the cheapest of arriving at either end of the last edge, or staying on the first edge if they are the same
+		let via_from_node = (distances[end_edge.from_node] + end_distance, end_edge.from_node, false);
+		let via_to_node = (distances[end_edge.to_node] + end_edge.line.mag - end_distance, end_edge.to_node, true);
+		let (length, last_node, arrive_from_end) = if via_from_node.0 <= via_to_node.0 { via_from_node } else { via_to_node };
+		if from.edge == to.edge && (end_distance - start_distance).abs() <= length {
+			let fraction = |distance: f64| if start_edge.line.mag > 0f64 { distance / start_edge.line.mag } else { 0f64 };
+			let path_edge = PathEdge { edge: from.edge, start_fraction: fraction(start_distance), end_fraction: fraction(end_distance) };
+			return Some(NetworkPath {
+				line: start_edge.line.substring_at_distance(start_distance, end_distance)?,
+				edges: vec![path_edge],
+				length: (end_distance - start_distance).abs(),
+			});
+		}
+		if !length.is_finite() {
+			return None;
+		}
+
+		let mut path_edges = vec![PathEdge {
+			edge: to.edge,
+			start_fraction: if arrive_from_end { 1f64 } else { 0f64 },
+			end_fraction: to.located.fraction_of_length,
+		}];
+		let mut node = last_node;
+		loop {
+			match previous[node]? {
+				Step::Edge { edge, from_node } => {
+					let forwards = self.edges[edge].from_node == from_node && self.edges[edge].to_node == node;
+					path_edges.push(PathEdge {
+						edge,
+						start_fraction: if forwards { 0f64 } else { 1f64 },
+						end_fraction: if forwards { 1f64 } else { 0f64 },
+					});
+					node = from_node;
+				}
+				Step::Start { towards_end } => {
+					path_edges.push(PathEdge {
+						edge: from.edge,
+						start_fraction: from.located.fraction_of_length,
+						end_fraction: if towards_end { 1f64 } else { 0f64 },
+					});
+					break;
+				}
+			}
+		}
+		path_edges.reverse();
+		// drop the zero length pieces left when a location is exactly on a node
+		path_edges.retain(|path_edge| path_edge.start_fraction != path_edge.end_fraction);
+
+		let mut points: Vec<Vector2> = Vec::new();
+		for path_edge in &path_edges {
+			let piece = self.edges[path_edge.edge].line.substring(path_edge.start_fraction, path_edge.end_fraction)?;
+			let piece_points: Vec<Vector2> = (&piece).into();
+			let skip = match points.last() {
+				Some(&last) if last == piece_points[0] => 1,
+				_ => 0,
+			};
+			points.extend_from_slice(&piece_points[skip..]);
+		}
+		Some(NetworkPath {
+			line: points.into(),
+			edges: path_edges,
+			length,
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Step {
+	/// The node was reached directly from the start location, travelling towards the start or end of the first edge
+	Start { towards_end: bool },
+	/// The node was reached along an edge from another node
+	Edge { edge: usize, from_node: usize },
+}
+
+/// A node waiting in the priority queue. Ordered so that `BinaryHeap` pops the smallest distance first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Visit {
+	distance: f64,
+	node: usize,
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.distance.partial_cmp(&self.distance).unwrap_or(Ordering::Equal).then_with(|| other.node.cmp(&self.node))
+	}
+}
+
+impl PartialOrd for Visit {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A square of four roads from (0, 0) to (10, 10), drawn in mixed directions with slightly mismatched corners,
+	/// plus a separate road which is not connected.
+	fn square() -> Network {
+		Network::new(
+			vec![
+				vec![Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0)].into(),
+				vec![Vector2::new(10.0, 10.0), Vector2::new(10.0, 0.001)].into(),
+				vec![Vector2::new(10.0, 10.0), Vector2::new(0.0, 10.0)].into(),
+				vec![Vector2::new(0.0, 0.0), Vector2::new(0.0, 10.0)].into(),
+				vec![Vector2::new(20.0, 20.0), Vector2::new(30.0, 20.0)].into(),
+			],
+			0.01,
+		)
+	}
+
+	#[test]
+	fn snapping() {
+		let network = square();
+		assert_eq!(network.nodes.len(), 6);
+		assert_eq!((network.edges[0].from_node, network.edges[0].to_node), (0, 1));
+		assert_eq!((network.edges[1].from_node, network.edges[1].to_node), (2, 1));
+		assert_eq!((network.edges[3].from_node, network.edges[3].to_node), (0, 3));
+	}
+
+	#[test]
+	fn snapping_across_cells() {
+		// end points either side of a cell boundary still snap, and points just over the tolerance apart do not
+		let network = Network::new(
+			vec![
+				vec![Vector2::new(-0.004, 0.0), Vector2::new(10.0, 0.0)].into(),
+				vec![Vector2::new(0.004, 0.0), Vector2::new(0.0, 10.0)].into(),
+				vec![Vector2::new(10.0, 10.0), Vector2::new(0.0, 10.011)].into(),
+				vec![Vector2::new(f64::NAN, 0.0), Vector2::new(-0.001, -0.001)].into(),
+			],
+			0.01,
+		);
+		assert_eq!(network.nodes.len(), 6);
+		assert_eq!(network.edges[1].from_node, 0);
+		assert_eq!(network.edges[2].to_node, 4);
+		assert_eq!(network.edges[3].to_node, 0);
+		// a NaN line can not be closest
+		assert_eq!(network.locate(Vector2::new(5.0, 1.0)).unwrap().edge, 0);
+	}
+
+	#[test]
+	fn path_around_corners() {
+		let network = square();
+		// from the bottom road to the right road, travelling the right road backwards
+		let path = network.shortest_path(Vector2::new(4.0, -1.0), Vector2::new(11.0, 7.0)).unwrap();
+		assert!((path.length - 13.0).abs() < 0.01);
+		assert_eq!(path.edges.len(), 2);
+		assert_eq!(path.edges[0], PathEdge { edge: 0, start_fraction: 0.4, end_fraction: 1.0 });
+		assert_eq!(path.edges[1].edge, 1);
+		assert_eq!(path.edges[1].start_fraction, 1.0);
+		assert!((path.edges[1].end_fraction - 0.3).abs() < 0.001);
+		let points: Vec<Vector2> = (&path.line).into();
+		assert_eq!(points.first(), Some(&Vector2::new(4.0, 0.0)));
+		assert_eq!(points[1], Vector2::new(10.0, 0.0));
+		assert!(points.last().unwrap().distance_to(Vector2::new(10.0, 7.0)) < 0.001);
+
+		// the shorter way round goes through the left road
+		let path = network.shortest_path(Vector2::new(1.0, 0.0), Vector2::new(1.0, 10.0)).unwrap();
+		assert_eq!(path.edges.iter().map(|path_edge| path_edge.edge).collect::<Vec<usize>>(), vec![0, 3, 2]);
+		assert_eq!(path.length, 12.0);
+		assert_eq!(path.line.into_tuples(), vec![(1.0, 0.0), (0.0, 0.0), (0.0, 10.0), (1.0, 10.0)]);
+	}
+
+	#[test]
+	fn path_on_one_edge() {
+		let network = square();
+		let path = network.shortest_path(Vector2::new(7.0, 1.0), Vector2::new(2.0, 1.0)).unwrap();
+		assert_eq!(path.edges, vec![PathEdge { edge: 0, start_fraction: 0.7, end_fraction: 0.2 }]);
+		assert_eq!(path.line.into_tuples(), vec![(7.0, 0.0), (2.0, 0.0)]);
+		assert_eq!(path.length, 5.0);
+	}
+
+	#[test]
+	fn disconnected() {
+		let network = square();
+		assert_eq!(network.shortest_path(Vector2::new(1.0, 0.0), Vector2::new(25.0, 21.0)), None);
+	}
+}
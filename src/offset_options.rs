@@ -0,0 +1,127 @@
+use crate::vector2::Vector2;
+
+/// How the offset is joined at corners.
+///
+/// At corners which turn towards the offset side the offset segments cross. A mitre joins them where they cross;
+/// a bevel, a round join or a mitre over the limit leaves a small loop past the crossing, which `offset` clips away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JoinStyle {
+	/// Extend both offset segments until they meet. Falls back to `Bevel` if the mitre is longer than the `mitre_limit`.
+	Mitre,
+	/// Connect the ends of the offset segments with a straight line.
+	Bevel,
+	/// Connect the ends of the offset segments with an arc centred on the original vertex.
+	Round(ArcTolerance),
+}
+
+/// Controls how closely arcs are approximated by straight chords.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArcTolerance {
+	/// The maximum angle in radians swept by each chord
+	Angle(f64),
+	/// The maximum distance between the middle of each chord and the true arc
+	Chord(f64),
+}
+
+/// How the ends of a buffer are closed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapStyle {
+	/// Close the ends with a straight line through the end point
+	Flat,
+	/// Extend the buffer past the end point by the buffer distance
+	Square,
+	/// Close the ends with a semicircle centred on the end point
+	Round(ArcTolerance),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OffsetOptions {
+	pub join: JoinStyle,
+	/// The maximum distance from an original vertex to its mitre point, as a multiple of the offset distance.
+	pub mitre_limit: f64,
+}
+
+impl Default for OffsetOptions {
+	/// Unlimited mitre joins. This matches the behaviour of `offset_basic` before options were introduced.
+	fn default() -> Self {
+		OffsetOptions {
+			join: JoinStyle::Mitre,
+			mitre_limit: f64::INFINITY,
+		}
+	}
+}
+
+impl OffsetOptions {
+	pub fn mitre(mitre_limit: f64) -> Self {
+		OffsetOptions { join: JoinStyle::Mitre, mitre_limit }
+	}
+
+	pub fn bevel() -> Self {
+		OffsetOptions { join: JoinStyle::Bevel, ..Default::default() }
+	}
+
+	pub fn round(tolerance: ArcTolerance) -> Self {
+		OffsetOptions { join: JoinStyle::Round(tolerance), ..Default::default() }
+	}
+
+	/// Returns the points which join the offset segment ending at `b` to the offset segment starting at `c`,
+	/// at a corner which turns away from the offset side if `outside`, or towards it otherwise.
+	pub(crate) fn join_points(&self, vertex: Vector2, b: Vector2, c: Vector2, mitre_point: Option<Vector2>, distance: f64, outside: bool) -> Vec<Vector2> {
+		match self.join {
+			JoinStyle::Mitre => match mitre_point {
+				Some(mitre_point) if mitre_point.distance_to(vertex) <= self.mitre_limit * distance.abs() => vec![mitre_point],
+				_ => vec![b, c],
+			},
+			JoinStyle::Bevel => vec![b, c],
+			JoinStyle::Round(tolerance) => {
+				let from = b - vertex;
+				let to = c - vertex;
+				// Outside corners always turn clockwise for a left (positive) offset, and inside corners anticlockwise
+				let turn = if outside { -distance.signum() } else { distance.signum() };
+				let sweep = turn * from.cross(to).abs().atan2(from.dot(to));
+				let mut points = vec![b];
+				points.extend(tolerance.arc(vertex, from, sweep));
+				points.push(c);
+				points
+			}
+		}
+	}
+}
+
+impl ArcTolerance {
+	/// The number of chords needed to approximate an arc sweeping `sweep` radians
+	pub fn chord_count(&self, radius: f64, sweep: f64) -> usize {
+		let max_step = match *self {
+			ArcTolerance::Angle(angle) => angle,
+			ArcTolerance::Chord(tolerance) if tolerance < radius => 2f64 * (1f64 - tolerance / radius).acos(),
+			ArcTolerance::Chord(_) => std::f64::consts::PI,
+		};
+		if max_step > 0f64 {
+			((sweep.abs() / max_step).ceil() as usize).max(1)
+		} else {
+			1
+		}
+	}
+
+	/// Returns the points strictly between the start and end of an arc about `centre`,
+	/// which starts at `centre + from` and sweeps anticlockwise by `sweep` radians.
+	pub(crate) fn arc(&self, centre: Vector2, from: Vector2, sweep: f64) -> Vec<Vector2> {
+		let chord_count = self.chord_count(from.magnitude(), sweep);
+		(1..chord_count)
+			.map(|index| centre + from.rotate(sweep * index as f64 / chord_count as f64))
+			.collect()
+	}
+}
+
+/// Linearly interpolates between `(fraction_of_length, distance)` breakpoints, which must be sorted by fraction of length.
+/// Returns the first or last distance outside the range of the breakpoints, or zero if there are no breakpoints.
+pub fn interpolate_breakpoints(breakpoints: &[(f64, f64)], fraction_of_length: f64) -> f64 {
+	let index = breakpoints.partition_point(|&(fraction, _)| fraction < fraction_of_length);
+	match (index.checked_sub(1).map(|index| breakpoints[index]), breakpoints.get(index)) {
+		(None, None) => 0f64,
+		(Some((_, distance)), None) | (None, Some(&(_, distance))) => distance,
+		(Some((fraction_a, distance_a)), Some(&(fraction_b, distance_b))) => {
+			distance_a + (distance_b - distance_a) * (fraction_of_length - fraction_a) / (fraction_b - fraction_a)
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use rayon::prelude::*;
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::located_point::LocatedPoint;
+use crate::offset_options::OffsetOptions;
+
+// Parallel versions of the batch operations, available with the `rayon` feature.
+// Results are always in the same order as the input.
+
+/// Same as `LineStringMeasured::offset_with_options`, for each of many linestrings.
+pub fn offset_all(lines: &[LineStringMeasured], distance: f64, options: &OffsetOptions) -> Vec<Option<Vec<LineStringMeasured>>> {
+	lines.par_iter().map(|line| line.offset_with_options(distance, options)).collect()
+}
+
+/// Same as `LineStringMeasured::offset_basic_with_options`, for each of many linestrings.
+pub fn offset_basic_all(lines: &[LineStringMeasured], distance: f64, options: &OffsetOptions) -> Vec<Option<Vec<Vector2>>> {
+	lines.par_iter().map(|line| line.offset_basic_with_options(distance, options)).collect()
+}
+
+/// Same as `LineStringMeasured::cut`, for each of many linestrings.
+pub fn cut_all(lines: &[LineStringMeasured], fraction_of_length: f64) -> Vec<(Option<LineStringMeasured>, Option<LineStringMeasured>)> {
+	lines.par_iter().map(|line| line.cut(fraction_of_length)).collect()
+}
+
+/// Same as `LineStringMeasured::interpolate`, for each of many linestrings.
+pub fn interpolate_all(lines: &[LineStringMeasured], fraction_of_length: f64) -> Vec<Option<Vector2>> {
+	lines.par_iter().map(|line| line.interpolate(fraction_of_length)).collect()
+}
+
+/// Same as `LineStringMeasured::locate`, for each pair of an index into `lines` and a point. Gives `None` if there is no line at the index.
+pub fn locate_all(lines: &[LineStringMeasured], queries: &[(usize, Vector2)]) -> Vec<Option<LocatedPoint>> {
+	queries.par_iter().map(|&(index, point)| lines.get(index)?.locate(point)).collect()
+}
+
+impl LineStringMeasured {
+	/// Same as `interpolate_many`, but runs in parallel.
+	pub fn par_interpolate_many(&self, fractions_of_length: &[f64]) -> Vec<Option<Vector2>> {
+		fractions_of_length.par_iter().map(|&fraction_of_length| self.interpolate(fraction_of_length)).collect()
+	}
+
+	/// Same as `direction_many`, but runs in parallel.
+	pub fn par_direction_many(&self, fractions_of_length: &[f64]) -> Vec<f64> {
+		fractions_of_length.par_iter().map(|&fraction_of_length| self.direction(fraction_of_length)).collect()
+	}
+
+	/// Same as `locate_many`, but runs in parallel.
+	pub fn par_locate_many(&self, points: &[Vector2]) -> Vec<Option<LocatedPoint>> {
+		points.par_iter().map(|&point| self.locate(point)).collect()
+	}
+
+	/// Same as `cut`, for many fractions of length, running in parallel.
+	pub fn par_cut_many(&self, fractions_of_length: &[f64]) -> Vec<(Option<LineStringMeasured>, Option<LineStringMeasured>)> {
+		fractions_of_length.par_iter().map(|&fraction_of_length| self.cut(fraction_of_length)).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn matches_sequential() {
+		let lines: Vec<LineStringMeasured> = (0..50)
+			.map(|index| {
+				let index = index as f64;
+				vec![Vector2::new(0.0, index), Vector2::new(10.0, index), Vector2::new(10.0, index + 10.0 + index)].into()
+			})
+			.collect();
+		let options = OffsetOptions::default();
+		assert_eq!(offset_all(&lines, 1.5, &options), lines.iter().map(|line| line.offset_with_options(1.5, &options)).collect::<Vec<_>>());
+		assert_eq!(offset_basic_all(&lines, -1.5, &options), lines.iter().map(|line| line.offset_basic_with_options(-1.5, &options)).collect::<Vec<_>>());
+		assert_eq!(cut_all(&lines, 0.3), lines.iter().map(|line| line.cut(0.3)).collect::<Vec<_>>());
+		assert_eq!(interpolate_all(&lines, 0.7), lines.iter().map(|line| line.interpolate(0.7)).collect::<Vec<_>>());
+		let queries: Vec<(usize, Vector2)> = (0..lines.len()).rev().map(|index| (index, Vector2::new(3.0, index as f64))).collect();
+		assert_eq!(
+			locate_all(&lines, &queries),
+			queries.iter().map(|&(index, point)| lines[index].locate(point)).collect::<Vec<_>>()
+		);
+		assert_eq!(locate_all(&lines, &[(50, Vector2::new(3.0, 4.0))]), vec![None]);
+
+		let line = &lines[7];
+		let fractions: Vec<f64> = (0..200).map(|index| ((index * 37) % 200) as f64 / 199.0).collect();
+		let points: Vec<Vector2> = fractions.iter().map(|&fraction| Vector2::new(fraction * 12.0, fraction * 30.0)).collect();
+		assert_eq!(line.par_interpolate_many(&fractions), line.interpolate_many(&fractions));
+		assert_eq!(line.par_direction_many(&fractions), line.direction_many(&fractions));
+		assert_eq!(line.par_locate_many(&points), line.locate_many(&points));
+		assert_eq!(line.par_cut_many(&fractions), fractions.iter().map(|&fraction| line.cut(fraction)).collect::<Vec<_>>());
+	}
+}
@@ -0,0 +1,441 @@
+use crate::vector2::Vector2;
+use crate::line_segment_measured::LineSegmentMeasured;
+
+// Helpers for working with bare `Vec<Vector2>` polylines. These are used by the offset algorithm
+// where the intermediate results are not worth measuring.
+
+pub(crate) fn segments(points: &[Vector2]) -> Vec<LineSegmentMeasured> {
+	points
+		.iter()
+		.zip(points.iter().skip(1))
+		.map(|(&a, &b)| LineSegmentMeasured { a, b, mag: a.distance_to(b) })
+		.collect()
+}
+
+/// A time along a segment at which to split it, and the point there.
+/// Both segments at a crossing get the same point, so the pieces on either side of it meet exactly.
+pub(crate) type Cut = (f64, Vector2);
+
+/// For each segment, records where it crosses any non-adjacent segment of the same polyline.
+pub(crate) fn add_self_intersection_cuts(segments: &[LineSegmentMeasured], cuts: &mut [Vec<Cut>]) {
+	for (i, j) in overlapping_self_pairs(segments) {
+		if j < i + 2 {
+			continue;
+		}
+		if let Some((point, time_i, time_j)) = segments[i].intersect_bounded(&segments[j]) {
+			cuts[i].push((time_i, point));
+			cuts[j].push((time_j, point));
+		}
+	}
+}
+
+/// For each segment, records where it crosses any of the `others`.
+pub(crate) fn add_intersection_cuts(segments: &[LineSegmentMeasured], others: &[LineSegmentMeasured], cuts: &mut [Vec<Cut>]) {
+	for (i, j) in overlapping_pairs(segments, others) {
+		if let Some((point, time, _)) = segments[i].intersect_bounded(&others[j]) {
+			cuts[i].push((time, point));
+		}
+	}
+}
+
+/// The corners of the bounding box of the segment, as (min, max)
+fn bounds(segment: &LineSegmentMeasured) -> (Vector2, Vector2) {
+	(
+		Vector2::new(segment.a.x.min(segment.b.x), segment.a.y.min(segment.b.y)),
+		Vector2::new(segment.a.x.max(segment.b.x), segment.a.y.max(segment.b.y)),
+	)
+}
+
+fn is_finite(segment: &LineSegmentMeasured) -> bool {
+	segment.a.is_finite() && segment.b.is_finite()
+}
+
+/// A bounding box which `overlapping_boxes` skips
+fn no_bounds() -> (Vector2, Vector2) {
+	(Vector2::new(f64::NAN, f64::NAN), Vector2::new(f64::NAN, f64::NAN))
+}
+
+/// The bounding box of the segment, or no box if either end is NaN or infinite.
+fn finite_bounds(segment: &LineSegmentMeasured) -> (Vector2, Vector2) {
+	if is_finite(segment) {
+		bounds(segment)
+	} else {
+		no_bounds()
+	}
+}
+
+/// Finds every pair `(i, j)` where the bounding boxes of `segments[i]` and `others[j]` overlap or touch.
+/// Segments with a NaN or infinite end are skipped, since they cannot intersect anything.
+pub(crate) fn overlapping_pairs(segments: &[LineSegmentMeasured], others: &[LineSegmentMeasured]) -> Vec<(usize, usize)> {
+	let boxes: Vec<(Vector2, Vector2)> = segments.iter().map(finite_bounds).collect();
+	let other_boxes: Vec<(Vector2, Vector2)> = others.iter().map(finite_bounds).collect();
+	overlapping_boxes(&boxes, &other_boxes)
+}
+
+/// Finds every pair `(i, j)` where `boxes[i]` and `other_boxes[j]` overlap or touch. Boxes are given as (min, max).
+/// Boxes are swept in order of their left edge, so only boxes whose x ranges overlap are compared.
+/// Boxes with a NaN corner are skipped.
+pub(crate) fn overlapping_boxes(boxes: &[(Vector2, Vector2)], other_boxes: &[(Vector2, Vector2)]) -> Vec<(usize, usize)> {
+	let is_valid = |(min, max): &(Vector2, Vector2)| !(min.x.is_nan() || min.y.is_nan() || max.x.is_nan() || max.y.is_nan());
+	// (left edge, is one of the others, index)
+	let mut starts: Vec<(f64, bool, usize)> = boxes
+		.iter()
+		.enumerate()
+		.filter(|(_, bounds)| is_valid(bounds))
+		.map(|(index, (min, _))| (min.x, false, index))
+		.chain(
+			other_boxes
+				.iter()
+				.enumerate()
+				.filter(|(_, bounds)| is_valid(bounds))
+				.map(|(index, (min, _))| (min.x, true, index)),
+		)
+		.collect();
+	starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+	let mut pairs = Vec::new();
+	let mut active: Vec<usize> = Vec::new();
+	let mut active_others: Vec<usize> = Vec::new();
+	for (left, is_other, index) in starts {
+		active.retain(|&i| boxes[i].1.x >= left);
+		active_others.retain(|&j| other_boxes[j].1.x >= left);
+		if is_other {
+			let (min, max) = other_boxes[index];
+			pairs.extend(active.iter().filter(|&&i| boxes[i].0.y <= max.y && min.y <= boxes[i].1.y).map(|&i| (i, index)));
+			active_others.push(index);
+		} else {
+			let (min, max) = boxes[index];
+			pairs.extend(active_others.iter().filter(|&&j| other_boxes[j].0.y <= max.y && min.y <= other_boxes[j].1.y).map(|&j| (index, j)));
+			active.push(index);
+		}
+	}
+	pairs
+}
+
+/// Like `overlapping_pairs` but within one list of segments. Each pair is returned once as `(i, j)` with `i < j`.
+pub(crate) fn overlapping_self_pairs(segments: &[LineSegmentMeasured]) -> Vec<(usize, usize)> {
+	let boxes: Vec<(Vector2, Vector2)> = segments.iter().map(bounds).collect();
+	let mut order: Vec<usize> = (0..segments.len()).filter(|&index| is_finite(&segments[index])).collect();
+	order.sort_by(|&a, &b| boxes[a].0.x.total_cmp(&boxes[b].0.x));
+
+	let mut pairs = Vec::new();
+	let mut active: Vec<usize> = Vec::new();
+	for index in order {
+		let (min, max) = boxes[index];
+		active.retain(|&i| boxes[i].1.x >= min.x);
+		pairs.extend(active.iter().filter(|&&i| boxes[i].0.y <= max.y && min.y <= boxes[i].1.y).map(|&i| (i.min(index), i.max(index))));
+		active.push(index);
+	}
+	pairs
+}
+
+/// Splits the polyline at the cuts recorded against each segment.
+/// Pieces which have no length are discarded.
+pub(crate) fn split_at_cuts(points: &[Vector2], mut cuts: Vec<Vec<Cut>>) -> Vec<Vec<Vector2>> {
+	let mut pieces = Vec::new();
+	let mut current = vec![points[0]];
+	for (index, segment_cuts) in cuts.iter_mut().enumerate() {
+		let a = points[index];
+		let b = points[index + 1];
+		segment_cuts.sort_by(|x, y| x.0.total_cmp(&y.0));
+		for &(time, point) in segment_cuts.iter() {
+			let cut_point = if time <= 0f64 {
+				a
+			} else if time >= 1f64 {
+				b
+			} else {
+				point
+			};
+			if current[current.len() - 1] != cut_point {
+				current.push(cut_point);
+			}
+			pieces.push(current);
+			current = vec![cut_point];
+		}
+		current.push(b);
+	}
+	pieces.push(current);
+	pieces.into_iter().filter(|piece| length(piece) > 0f64).collect()
+}
+
+pub(crate) fn length(points: &[Vector2]) -> f64 {
+	points.iter().zip(points.iter().skip(1)).map(|(a, b)| a.distance_to(*b)).sum()
+}
+
+/// Returns the point half way along the polyline
+pub(crate) fn midpoint(points: &[Vector2]) -> Vector2 {
+	let mut remaining = length(points) / 2f64;
+	for (&a, &b) in points.iter().zip(points.iter().skip(1)) {
+		let segment_length = a.distance_to(b);
+		if remaining <= segment_length && segment_length > 0f64 {
+			return a + (b - a) * (remaining / segment_length);
+		}
+		remaining -= segment_length;
+	}
+	points[points.len() - 1]
+}
+
+/// Joins each piece onto the previous piece if the previous piece ends where it starts.
+/// A single segment sliver touching the join is dropped.
+pub(crate) fn join_pieces(pieces: Vec<Vec<Vector2>>, tolerance: f64) -> Vec<Vec<Vector2>> {
+	let mut result: Vec<Vec<Vector2>> = Vec::new();
+	let mut pieces = pieces.into_iter().peekable();
+	while let Some(piece) = pieces.next() {
+		if let (Some(previous), Some(next)) = (result.last(), pieces.peek()) {
+			// a lone segment touching the end of the previous piece, where the next piece carries on, is a sliver
+			// left between two crossings so close together that rounding has lost their order along the offset
+			let end = previous[previous.len() - 1];
+			if piece.len() == 2
+				&& end.distance_to(next[0]) <= tolerance
+				&& (end.distance_to(piece[0]) <= tolerance || end.distance_to(piece[1]) <= tolerance)
+			{
+				continue;
+			}
+		}
+		match result.last_mut() {
+			Some(previous) if previous[previous.len() - 1].distance_to(piece[0]) <= tolerance => {
+				// pieces were cut apart mid-segment; don't leave a redundant vertex where they are rejoined
+				let before = previous[previous.len() - 2];
+				let junction = previous[previous.len() - 1];
+				let after = piece[1];
+				if (junction - before).cross(after - junction).abs() <= tolerance * (after - before).magnitude()
+					&& (junction - before).dot(after - junction) > 0f64
+				{
+					previous.pop();
+				}
+				previous.extend_from_slice(&piece[1..]);
+			}
+			_ => result.push(piece),
+		}
+	}
+	result
+}
+
+/// True if the point is inside the convex polygon, and further than `tolerance` from its boundary.
+pub(crate) fn convex_contains(polygon: &[Vector2], point: Vector2, tolerance: f64) -> bool {
+	let orientation = signed_area(polygon).signum();
+	polygon.iter().zip(polygon.iter().cycle().skip(1)).all(|(&a, &b)| {
+		let edge = b - a;
+		let edge_length = edge.magnitude();
+		edge_length == 0f64 || edge.cross(point - a) * orientation > tolerance * edge_length
+	})
+}
+
+/// For each point, true if it is inside any of the convex polygons, and further than `tolerance` from its boundary.
+pub(crate) fn points_in_convex_polygons(polygons: &[Vec<Vector2>], points: &[Vector2], tolerance: f64) -> Vec<bool> {
+	let polygon_boxes: Vec<(Vector2, Vector2)> = polygons
+		.iter()
+		.map(|polygon| {
+			polygon.iter().fold((Vector2::new(f64::INFINITY, f64::INFINITY), Vector2::new(f64::NEG_INFINITY, f64::NEG_INFINITY)), |(min, max), point| {
+				(Vector2::new(min.x.min(point.x), min.y.min(point.y)), Vector2::new(max.x.max(point.x), max.y.max(point.y)))
+			})
+		})
+		.collect();
+	let point_boxes: Vec<(Vector2, Vector2)> = points.iter().map(|&point| (point, point)).collect();
+	let mut result = vec![false; points.len()];
+	for (i, j) in overlapping_boxes(&point_boxes, &polygon_boxes) {
+		if !result[i] && convex_contains(&polygons[j], points[i], tolerance) {
+			result[i] = true;
+		}
+	}
+	result
+}
+
+/// For each point, true if it is closer than `radius` to the interior of any of the segments, measured perpendicular to that segment.
+/// Only segments whose bounding box is within `radius` of the point are checked.
+pub(crate) fn points_within_offset_band(segments: &[LineSegmentMeasured], points: &[Vector2], radius: f64) -> Vec<bool> {
+	let segment_boxes: Vec<(Vector2, Vector2)> = segments
+		.iter()
+		.map(|segment| if segment.mag > 0f64 { finite_bounds(segment) } else { no_bounds() })
+		.collect();
+	let reach = Vector2::new(radius, radius);
+	let point_boxes: Vec<(Vector2, Vector2)> = points.iter().map(|&point| (point - reach, point + reach)).collect();
+	let mut result = vec![false; points.len()];
+	for (i, j) in overlapping_boxes(&point_boxes, &segment_boxes) {
+		if !result[i] {
+			let (time, perpendicular_distance) = segments[j].project_unbounded(points[i]);
+			result[i] = time > 0f64 && time < 1f64 && perpendicular_distance.abs() < radius;
+		}
+	}
+	result
+}
+
+/// The signed area of the closed ring; positive when the ring is anticlockwise
+pub(crate) fn signed_area(ring: &[Vector2]) -> f64 {
+	ring.iter()
+		.zip(ring.iter().cycle().skip(1))
+		.map(|(a, b)| a.cross(*b))
+		.sum::<f64>()
+		/ 2f64
+}
+
+/// Returns the middle of the longest segment of the polyline, and the unit direction of that segment.
+/// Unlike the `midpoint`, this is never at a vertex.
+pub(crate) fn longest_segment_midpoint(points: &[Vector2]) -> (Vector2, Vector2) {
+	let (a, b) = points
+		.iter()
+		.zip(points.iter().skip(1))
+		.max_by(|(a, b), (c, d)| a.distance_to(**b).total_cmp(&c.distance_to(**d)))
+		.unwrap();
+	((*a + *b) / 2f64, (b - a).unit())
+}
+
+/// The number of times the closed ring winds anticlockwise around the point
+pub(crate) fn winding_number(ring: &[Vector2], point: Vector2) -> i32 {
+	ring.iter().zip(ring.iter().cycle().skip(1)).map(|(&a, &b)| crossing(a, b, point)).sum()
+}
+
+/// +1 if the edge crosses upwards to the right of the point, -1 if it crosses downwards, otherwise 0
+fn crossing(a: Vector2, b: Vector2, point: Vector2) -> i32 {
+	let side = (b - a).cross(point - a);
+	if a.y <= point.y {
+		if b.y > point.y && side > 0f64 {
+			return 1;
+		}
+	} else if b.y <= point.y && side < 0f64 {
+		return -1;
+	}
+	0
+}
+
+/// For each point, the total number of times the closed rings wind anticlockwise around it.
+/// The edges are put into horizontal strips, so that only the edges level with each point are checked.
+pub(crate) fn winding_numbers(rings: &[Vec<Vector2>], points: &[Vector2]) -> Vec<i32> {
+	let edges: Vec<(Vector2, Vector2)> = rings.iter().flat_map(|ring| ring.iter().copied().zip(ring.iter().copied().cycle().skip(1))).collect();
+	let bottom = edges.iter().fold(f64::INFINITY, |bottom, (a, b)| bottom.min(a.y).min(b.y));
+	let top = edges.iter().fold(f64::NEG_INFINITY, |top, (a, b)| top.max(a.y).max(b.y));
+	let strip_count = ((edges.len() as f64).sqrt().ceil() as usize).max(1);
+	let strip_height = (top - bottom) / strip_count as f64;
+	let strip_of = |y: f64| -> usize {
+		if strip_height > 0f64 {
+			(((y - bottom) / strip_height) as usize).min(strip_count - 1)
+		} else {
+			0
+		}
+	};
+	let mut strips: Vec<Vec<usize>> = vec![Vec::new(); strip_count];
+	for (index, (a, b)) in edges.iter().enumerate() {
+		for strip in &mut strips[strip_of(a.y.min(b.y))..=strip_of(a.y.max(b.y))] {
+			strip.push(index);
+		}
+	}
+	points
+		.iter()
+		.map(|&point| {
+			if !(bottom..=top).contains(&point.y) {
+				return 0;
+			}
+			strips[strip_of(point.y)].iter().map(|&index| crossing(edges[index].0, edges[index].1, point)).sum()
+		})
+		.collect()
+}
+
+/// Resolves self intersecting rings into simple rings enclosing the region where the total winding number of the rings is positive.
+/// Pieces of the boundary are discarded if `keep` returns false for their midpoint; it is given the midpoints of all the pieces at once.
+/// Returned rings are closed (the first point is repeated at the end).
+/// Anticlockwise exteriors come first, largest first, followed by clockwise holes.
+pub(crate) fn positive_rings<F: Fn(&[Vector2]) -> Vec<bool>>(raw_rings: &[Vec<Vector2>], tolerance: f64, keep: F) -> Vec<Vec<Vector2>> {
+	let closed_rings: Vec<Vec<Vector2>> = raw_rings
+		.iter()
+		.filter(|ring| ring.len() > 2)
+		.map(|ring| {
+			let mut closed = ring.clone();
+			closed.push(ring[0]);
+			closed
+		})
+		.collect();
+	// The segments of every ring in one list, and the (ring, segment) each one came from
+	let all_segments: Vec<LineSegmentMeasured> = closed_rings.iter().flat_map(|ring| segments(ring)).collect();
+	let owners: Vec<(usize, usize)> = closed_rings
+		.iter()
+		.enumerate()
+		.flat_map(|(ring_index, ring)| (0..ring.len() - 1).map(move |index| (ring_index, index)))
+		.collect();
+	let mut cuts: Vec<Vec<Vec<Cut>>> = closed_rings.iter().map(|ring| vec![Vec::new(); ring.len() - 1]).collect();
+	for (i, j) in overlapping_self_pairs(&all_segments) {
+		let (ring_i, index_i) = owners[i];
+		let (ring_j, index_j) = owners[j];
+		if ring_i == ring_j && index_j < index_i + 2 {
+			continue;
+		}
+		if let Some((point, time_i, time_j)) = all_segments[i].intersect_bounded(&all_segments[j]) {
+			cuts[ring_i][index_i].push((time_i, point));
+			cuts[ring_j][index_j].push((time_j, point));
+		}
+	}
+
+	let pieces: Vec<Vec<Vector2>> = closed_rings.iter().zip(cuts).flat_map(|(ring, ring_cuts)| split_at_cuts(ring, ring_cuts)).collect();
+	let midpoints: Vec<(Vector2, Vector2)> = pieces.iter().map(|piece| longest_segment_midpoint(piece)).collect();
+	let probes: Vec<Vector2> = midpoints
+		.iter()
+		.flat_map(|&(midpoint, direction)| {
+			let probe = direction.left() * tolerance;
+			vec![midpoint + probe, midpoint - probe]
+		})
+		.collect();
+	let winding = winding_numbers(&closed_rings, &probes);
+	let kept = keep(&midpoints.iter().map(|&(midpoint, _)| midpoint).collect::<Vec<Vector2>>());
+	let boundary_pieces = pieces
+		.into_iter()
+		.zip(winding.chunks(2).zip(kept))
+		.filter_map(|(mut piece, (winding, kept))| {
+			let filled_left = winding[0] > 0;
+			let filled_right = winding[1] > 0;
+			if filled_left == filled_right || !kept {
+				None
+			} else {
+				if filled_right {
+					piece.reverse();
+				}
+				Some(piece)
+			}
+		})
+		.collect();
+
+	let mut rings = stitch_rings(boundary_pieces, tolerance);
+	rings.sort_by(|a, b| signed_area(b).total_cmp(&signed_area(a)));
+	rings
+}
+
+/// A `keep` for `positive_rings` which keeps every piece
+pub(crate) fn keep_all(points: &[Vector2]) -> Vec<bool> {
+	vec![true; points.len()]
+}
+
+/// Groups rings sorted by `positive_rings` into polygons. Each polygon is an exterior followed by the holes inside it.
+pub(crate) fn group_polygons(rings: Vec<Vec<Vector2>>) -> Vec<Vec<Vec<Vector2>>> {
+	let (exteriors, holes): (Vec<Vec<Vector2>>, Vec<Vec<Vector2>>) = rings.into_iter().partition(|ring| signed_area(ring) > 0f64);
+	let mut polygons: Vec<Vec<Vec<Vector2>>> = exteriors.into_iter().map(|exterior| vec![exterior]).collect();
+	for hole in holes {
+		let (inside_point, _) = longest_segment_midpoint(&hole);
+		// exteriors are sorted largest first, so the last one containing the hole is the smallest
+		if let Some(polygon) = polygons.iter_mut().rev().find(|polygon| winding_number(&polygon[0], inside_point) != 0) {
+			polygon.push(hole);
+		}
+	}
+	polygons
+}
+
+/// Joins pieces end to start until each forms a closed ring. Pieces which cannot be closed are discarded.
+pub(crate) fn stitch_rings(mut pieces: Vec<Vec<Vector2>>, tolerance: f64) -> Vec<Vec<Vector2>> {
+	let mut rings = Vec::new();
+	while let Some(mut ring) = pieces.pop() {
+		loop {
+			let end = ring[ring.len() - 1];
+			if ring.len() > 2 && end.distance_to(ring[0]) <= tolerance {
+				let ring_length = ring.len();
+				ring[ring_length - 1] = ring[0];
+				rings.push(ring);
+				break;
+			}
+			match pieces.iter().position(|piece| piece[0].distance_to(end) <= tolerance) {
+				Some(index) => {
+					let piece = pieces.swap_remove(index);
+					ring.extend_from_slice(&piece[1..]);
+				}
+				None => break,
+			}
+		}
+	}
+	rings
+}
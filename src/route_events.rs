@@ -0,0 +1,254 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vector2::Vector2;
+use crate::line_string_m::LineStringM;
+use crate::line_string_measured::LineStringMeasured;
+use crate::offset_options::OffsetOptions;
+
+/// The attributes carried by an event, serialised as a JSON object like GeoJSON feature properties.
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// One attribute of an event. Serialised as the matching JSON value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+}
+
+impl From<bool> for AttributeValue {
+	fn from(other: bool) -> Self {
+		AttributeValue::Bool(other)
+	}
+}
+
+impl From<f64> for AttributeValue {
+	fn from(other: f64) -> Self {
+		AttributeValue::Number(other)
+	}
+}
+
+impl From<&str> for AttributeValue {
+	fn from(other: &str) -> Self {
+		AttributeValue::String(other.to_string())
+	}
+}
+
+impl From<String> for AttributeValue {
+	fn from(other: String) -> Self {
+		AttributeValue::String(other)
+	}
+}
+
+/// Something which happens at a single measure along a route, such as a crash or a sign.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointEvent {
+	pub route: String,
+	pub measure: f64,
+	/// Perpendicular offset from the route, positive on the left
+	#[serde(default)]
+	pub offset: f64,
+	#[serde(default)]
+	pub attributes: Attributes,
+}
+
+/// Something which applies between two measures along a route, such as a pavement type or speed zone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinearEvent {
+	pub route: String,
+	pub from_measure: f64,
+	pub to_measure: f64,
+	/// Perpendicular offset from the route, positive on the left
+	#[serde(default)]
+	pub offset: f64,
+	#[serde(default)]
+	pub attributes: Attributes,
+}
+
+impl PointEvent {
+	pub fn new(route: &str, measure: f64, attributes: Attributes) -> PointEvent {
+		PointEvent { route: route.to_string(), measure, offset: 0f64, attributes }
+	}
+}
+
+impl LinearEvent {
+	pub fn new(route: &str, from_measure: f64, to_measure: f64, attributes: Attributes) -> LinearEvent {
+		LinearEvent { route: route.to_string(), from_measure, to_measure, offset: 0f64, attributes }
+	}
+}
+
+/// The reason an event could not be turned into geometry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventError {
+	/// There is no route with this id
+	UnknownRoute(String),
+	/// The measure is not between the first and last measures of the route
+	MeasureOutOfRange { measure: f64, from_measure: f64, to_measure: f64 },
+	/// The event covers no length of the route, or its offset geometry is empty
+	EmptyGeometry,
+}
+
+impl fmt::Display for EventError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			EventError::UnknownRoute(route) => write!(f, "unknown route '{}'", route),
+			EventError::MeasureOutOfRange { measure, from_measure, to_measure } => {
+				write!(f, "measure {} is not on the route, which is measured from {} to {}", measure, from_measure, to_measure)
+			}
+			EventError::EmptyGeometry => write!(f, "the event has no geometry"),
+		}
+	}
+}
+
+impl std::error::Error for EventError {}
+
+/// Turns point and linear events into geometry by looking up their measures on a set of calibrated routes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicSegmentation {
+	pub routes: HashMap<String, LineStringM>,
+	/// Used when offsetting linear events
+	pub options: OffsetOptions,
+}
+
+impl DynamicSegmentation {
+	pub fn new(routes: HashMap<String, LineStringM>) -> DynamicSegmentation {
+		DynamicSegmentation { routes, options: OffsetOptions::default() }
+	}
+
+	pub fn with_options(routes: HashMap<String, LineStringM>, options: OffsetOptions) -> DynamicSegmentation {
+		DynamicSegmentation { routes, options }
+	}
+
+	pub fn route(&self, route: &str) -> Result<&LineStringM, EventError> {
+		self.routes.get(route).ok_or_else(|| EventError::UnknownRoute(route.to_string()))
+	}
+
+	/// Gets the location of a point event, moved perpendicular to the route by its offset.
+	pub fn locate_point(&self, event: &PointEvent) -> Result<Vector2, EventError> {
+		let route = self.route(&event.route)?;
+		let point = route.interpolate(event.measure).ok_or_else(|| out_of_range(route, event.measure))?;
+		if event.offset == 0f64 {
+			return Ok(point);
+		}
+		let direction = route.direction(event.measure).ok_or_else(|| out_of_range(route, event.measure))?;
+		Ok(point + Vector2::new(direction.cos(), direction.sin()).left() * event.offset)
+	}
+
+	/// Gets the part of the route covered by a linear event, offset by `offset_basic` if the event has an offset.
+	/// If `to_measure` comes before `from_measure` on the route the result runs backwards, and a positive offset is still on the left of the result.
+	pub fn locate_linear(&self, event: &LinearEvent) -> Result<LineStringMeasured, EventError> {
+		let route = self.route(&event.route)?;
+		let distance_along = |measure: f64| route.distance_along_at_measure(measure).ok_or_else(|| out_of_range(route, measure));
+		// routes may be measured in either direction, so cut in the order the measures occur along the route
+		let backwards = distance_along(event.from_measure)? > distance_along(event.to_measure)?;
+		let line = if backwards {
+			route.cut_twice(event.to_measure, event.from_measure).ok_or(EventError::EmptyGeometry)?.line.reversed()
+		} else {
+			route.cut_twice(event.from_measure, event.to_measure).ok_or(EventError::EmptyGeometry)?.line
+		};
+		if event.offset == 0f64 {
+			return Ok(line);
+		}
+		match line.offset_basic_with_options(event.offset, &self.options) {
+			Some(points) if points.len() > 1 => Ok(points.into()),
+			_ => Err(EventError::EmptyGeometry),
+		}
+	}
+
+	/// Locates every event, keeping the results in the same order as the events.
+	pub fn locate_points(&self, events: &[PointEvent]) -> Vec<Result<Vector2, EventError>> {
+		events.iter().map(|event| self.locate_point(event)).collect()
+	}
+
+	/// Locates every event, keeping the results in the same order as the events.
+	pub fn locate_linears(&self, events: &[LinearEvent]) -> Vec<Result<LineStringMeasured, EventError>> {
+		events.iter().map(|event| self.locate_linear(event)).collect()
+	}
+}
+
+fn out_of_range(route: &LineStringM, measure: f64) -> EventError {
+	EventError::MeasureOutOfRange {
+		measure,
+		from_measure: route.from_measure().unwrap_or(f64::NAN),
+		to_measure: route.to_measure().unwrap_or(f64::NAN),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{json, Value};
+
+	/// Route "A" runs east then north and is measured in hundreds; route "B" is measured backwards.
+	fn segmentation() -> DynamicSegmentation {
+		let mut routes = HashMap::new();
+		routes.insert("A".to_string(), LineStringM::from(vec![(0.0, 0.0, 0.0), (10.0, 0.0, 1000.0), (10.0, 10.0, 2000.0)]));
+		routes.insert("B".to_string(), LineStringM::from(vec![(0.0, 20.0, 50.0), (10.0, 20.0, 0.0)]));
+		DynamicSegmentation::new(routes)
+	}
+
+	fn attributes(value: Value) -> Attributes {
+		serde_json::from_value(value).unwrap()
+	}
+
+	#[test]
+	fn point_events() {
+		let segmentation = segmentation();
+		let crash = PointEvent::new("A", 1500.0, attributes(json!({"severity": "minor"})));
+		assert_eq!(segmentation.locate_point(&crash), Ok(Vector2::new(10.0, 5.0)));
+		let offset_crash = PointEvent { offset: 2.0, ..crash.clone() };
+		let point = segmentation.locate_point(&offset_crash).unwrap();
+		assert!(point.distance_to(Vector2::new(8.0, 5.0)) < 1e-9);
+		assert_eq!(segmentation.locate_point(&PointEvent::new("B", 10.0, Attributes::new())), Ok(Vector2::new(8.0, 20.0)));
+
+		assert_eq!(
+			segmentation.locate_points(&[PointEvent::new("C", 0.0, Attributes::new()), PointEvent::new("A", 2500.0, Attributes::new())]),
+			vec![
+				Err(EventError::UnknownRoute("C".to_string())),
+				Err(EventError::MeasureOutOfRange { measure: 2500.0, from_measure: 0.0, to_measure: 2000.0 }),
+			]
+		);
+	}
+
+	#[test]
+	fn linear_events() {
+		let segmentation = segmentation();
+		let zone = LinearEvent::new("A", 500.0, 1500.0, attributes(json!({"speed": 60})));
+		assert_eq!(segmentation.locate_linear(&zone).unwrap().into_tuples(), vec![(5.0, 0.0), (10.0, 0.0), (10.0, 5.0)]);
+
+		let backwards = LinearEvent::new("A", 1500.0, 500.0, Attributes::new());
+		assert_eq!(segmentation.locate_linear(&backwards).unwrap().into_tuples(), vec![(10.0, 5.0), (10.0, 0.0), (5.0, 0.0)]);
+
+		let offset_zone = LinearEvent { offset: 1.0, ..zone };
+		assert_eq!(segmentation.locate_linear(&offset_zone).unwrap().into_tuples(), vec![(5.0, 1.0), (9.0, 1.0), (9.0, 5.0)]);
+
+		assert_eq!(segmentation.locate_linear(&LinearEvent::new("B", 40.0, 20.0, Attributes::new())).unwrap().into_tuples(), vec![(2.0, 20.0), (6.0, 20.0)]);
+		assert_eq!(segmentation.locate_linear(&LinearEvent::new("A", 500.0, 500.0, Attributes::new())), Err(EventError::EmptyGeometry));
+	}
+
+	#[test]
+	fn events_from_json() {
+		let event: LinearEvent = serde_json::from_value(json!({"route": "A", "from_measure": 0, "to_measure": 100})).unwrap();
+		assert_eq!(event, LinearEvent::new("A", 0.0, 100.0, Attributes::new()));
+
+		let event: PointEvent = serde_json::from_value(json!({
+			"route": "A",
+			"measure": 5,
+			"attributes": {"severity": "minor", "vehicles": 2, "fatal": false, "notes": null}
+		}))
+		.unwrap();
+		assert_eq!(event.attributes["severity"], AttributeValue::from("minor"));
+		assert_eq!(event.attributes["vehicles"], AttributeValue::from(2.0));
+		assert_eq!(event.attributes["fatal"], AttributeValue::from(false));
+		assert_eq!(event.attributes["notes"], AttributeValue::Null);
+		assert_eq!(
+			serde_json::to_value(&event.attributes).unwrap(),
+			json!({"fatal": false, "notes": null, "severity": "minor", "vehicles": 2.0})
+		);
+	}
+}
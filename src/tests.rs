@@ -1,8 +1,15 @@
 
 // use crate::LineString::{LineString, LineStringMeasured, LineStringy};
 use crate::line_string_measured::{LineStringMeasured};
+use crate::line_string_m::LineStringM;
+use crate::multi_line_string_measured::{MultiLineStringMeasured, Gaps};
 use crate::line_segment_measured::{LineSegmentMeasured};
 use crate::vector2::Vector2;
+use crate::offset_options::{OffsetOptions, ArcTolerance, CapStyle};
+use crate::located_point::{LocatedPoint, Side};
+use crate::error::LineStringError;
+use crate::validation_report::ValidationReport;
+use crate::polyline;
 
 #[test]
 fn test_linestring_length() {
@@ -62,7 +69,8 @@ fn test_measured() {
 					mag: 1.0f64
 				}
 			],
-			mag: 2f64
+			mag: 2f64,
+			cumulative: vec![1.0, 2.0]
 		}
 	);
 }
@@ -93,7 +101,8 @@ fn test_cut() {
 						mag: 0.5f64
 					}
 				],
-				mag: 1.5f64
+				mag: 1.5f64,
+				cumulative: vec![1.0, 1.5]
 			}),
 			Some(LineStringMeasured {
 				segments: vec![
@@ -108,7 +117,8 @@ fn test_cut() {
 						mag: 1.0f64
 					}
 				],
-				mag: 1.5f64
+				mag: 1.5f64,
+				cumulative: vec![0.5, 1.5]
 			})
 		)
 	);
@@ -143,7 +153,8 @@ fn test_cut() {
 					mag: 1.0f64
 				}
 			],
-			mag: 2f64
+			mag: 2f64,
+			cumulative: vec![1.0, 2.0]
 		})
 	);
 	assert_eq!(
@@ -161,7 +172,8 @@ fn test_cut() {
 					mag: 1.0f64
 				}
 			],
-			mag: 2f64
+			mag: 2f64,
+			cumulative: vec![1.0, 2.0]
 		})
 	);
 }
@@ -192,7 +204,8 @@ fn test_double_cut() {
 					mag: 1.0f64
 				}
 			],
-			mag: 2f64
+			mag: 2f64,
+			cumulative: vec![1.0, 2.0]
 		})
 	);
 	assert_eq!(
@@ -203,7 +216,8 @@ fn test_double_cut() {
 				b: Vector2::new(0.0, 1.0),
 				mag: 1.0f64
 			},],
-			mag: 1f64
+			mag: 1f64,
+			cumulative: vec![1.0]
 		})
 	);
 	assert_eq!(
@@ -214,7 +228,8 @@ fn test_double_cut() {
 				b: Vector2::new(0.0, 2.0),
 				mag: 1.0f64
 			},],
-			mag: 1f64
+			mag: 1f64,
+			cumulative: vec![1.0]
 		})
 	);
 }
@@ -251,6 +266,7 @@ fn test_interpolate() {
 
 
 #[test]
+#[allow(clippy::useless_conversion)]
 fn test_linestring_basic_offset() {
 	// let ls = vec![
 	// 	Vector2::new(0.0, 0.0),
@@ -295,6 +311,7 @@ fn test_linestring_basic_offset() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_linestring_conversion_to_vec() {
 	let ls:LineStringMeasured = vec![
 		Vector2::new(0.0, 0.0),
@@ -362,3 +379,811 @@ fn test_multilinestring_conversion_to_measured_tuples() {
 	);
 
 }
+
+#[test]
+fn test_offset_removes_loops_in_tight_hairpin() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 1.0),
+		Vector2::new(0.0, 1.0),
+	].into();
+
+	// The hairpin is narrower than twice the offset, so nothing survives on the inside.
+	let inside = ls.offset(2f64).unwrap();
+	assert_eq!(inside, vec![]);
+
+	let outside = ls.offset(-2f64).unwrap();
+	assert_eq!(
+		outside.iter().map(|part| part.into_tuples()).collect::<Vec<_>>(),
+		vec![vec![
+			(0.0, -2.0),
+			(12.0, -2.0),
+			(12.0, 3.0),
+			(0.0, 3.0),
+		]]
+	);
+}
+
+#[test]
+fn test_offset_keeps_valid_parts() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(9.0, 10.0),
+		Vector2::new(9.0, 20.0),
+	].into();
+	// the little 1 unit jog at the top produces a loop in the raw offset which must be removed
+	let raw = ls.offset_basic(-2f64).unwrap();
+	let result = ls.offset(-2f64).unwrap();
+	assert!(raw.len() > 4);
+	assert_eq!(result.len(), 1);
+	let result = result[0].into_tuples();
+	assert_eq!(result.first(), Some(&(0.0, -2.0)));
+	assert_eq!(result.last(), Some(&(11.0, 20.0)));
+	for (x, y) in result {
+		let distance_to_original = ls.segments.iter().map(|segment| {
+			let (time, perpendicular) = segment.project_unbounded(Vector2::new(x, y));
+			if (0f64..=1f64).contains(&time) {perpendicular.abs()} else {f64::INFINITY}
+		}).fold(f64::INFINITY, f64::min);
+		assert!(distance_to_original >= 2f64 - 0.000001);
+	}
+}
+
+#[test]
+fn test_offset_shallow_self_crossing() {
+	// the offset of each gentle bend crosses itself at a very shallow angle,
+	// so the pieces on either side of a crossing must meet exactly to be joined back up
+	for &count in &[8000, 20000] {
+		let ls:LineStringMeasured = (0..count)
+			.map(|index| {
+				let t = index as f64 * 0.05;
+				Vector2::new(t, 3.0 * (0.7 * t).sin())
+			})
+			.collect::<Vec<Vector2>>()
+			.into();
+		for &distance in &[2f64, -2f64, -1.2f64] {
+			assert_eq!(ls.offset(distance).unwrap().len(), 1);
+		}
+	}
+}
+
+#[test]
+fn test_offset_and_buffer_with_repeated_vertex() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(5.0, 0.0),
+		Vector2::new(5.0, 0.0),
+		Vector2::new(10.0, 3.0),
+	].into();
+	// the repeated vertex gives a zero length segment, which has no direction to offset in
+	let offset = ls.offset(1f64).unwrap();
+	assert_eq!(offset.len(), 1);
+	assert!(offset[0].into_tuples().iter().all(|(x, y)| x.is_finite() && y.is_finite()));
+	assert_eq!(offset[0].into_tuples()[0], (0.0, 1.0));
+	let buffer = ls.buffer(1f64, CapStyle::Square, &OffsetOptions::default()).unwrap();
+	assert_eq!(buffer.len(), 1);
+	assert!(buffer[0].iter().all(|point| point.is_finite()));
+}
+
+#[test]
+fn test_offset_with_non_finite_point() {
+	// a NaN segment has a NaN length, so it must not be mistaken for a zero length segment and dropped
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(5.0, 0.0),
+		Vector2::new(f64::NAN, 0.0),
+		Vector2::new(10.0, 3.0),
+	].into();
+	assert_eq!(ls.offset_basic(1f64), None);
+	assert_eq!(ls.offset(1f64), None);
+	assert_eq!(ls.try_offset(1f64, &OffsetOptions::default()), Err(LineStringError::NonFinite));
+	assert_eq!(ls.buffer(1f64, CapStyle::Flat, &OffsetOptions::default()), None);
+	assert_eq!(ls.buffer(0f64, CapStyle::Flat, &OffsetOptions::default()), None);
+	assert_eq!(ls.buffer_one_sided(1f64, &OffsetOptions::default()), None);
+}
+
+#[test]
+fn test_offset_basic_join_styles() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(0.0, 1.0),
+	].into();
+	let vertex = Vector2::new(10.0, 0.0);
+
+	// Unlimited mitre spikes far past the vertex
+	let mitre = ls.offset_basic(-1f64).unwrap();
+	assert_eq!(mitre.len(), 3);
+	assert!(mitre[1].distance_to(vertex) > 10f64);
+
+	let limited = ls.offset_basic_with_options(-1f64, &OffsetOptions::mitre(2f64)).unwrap();
+	let bevel = ls.offset_basic_with_options(-1f64, &OffsetOptions::bevel()).unwrap();
+	assert_eq!(limited, bevel);
+	assert_eq!(bevel.len(), 4);
+	assert_eq!(bevel[1], Vector2::new(10.0, -1.0));
+
+	let round = ls.offset_basic_with_options(-1f64, &OffsetOptions::round(ArcTolerance::Angle(std::f64::consts::FRAC_PI_4))).unwrap();
+	// the turn is nearly 180 degrees so it takes 4 chords
+	assert_eq!(round.len(), 7);
+	for point in &round[1..6] {
+		assert!((point.distance_to(vertex) - 1f64).abs() < 0.000000001);
+		assert!(point.x >= 10f64);
+	}
+
+	let round = ls.offset_basic_with_options(-1f64, &OffsetOptions::round(ArcTolerance::Chord(0.01))).unwrap();
+	for (a, b) in round[1..].iter().zip(round[2..round.len() - 1].iter()) {
+		let chord_middle = (*a + *b) / 2f64;
+		assert!(chord_middle.distance_to(vertex) >= 0.99);
+	}
+}
+
+#[test]
+fn test_offset_basic_join_styles_inside_corner() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+	].into();
+	let mitre = vec![Vector2::new(0.0, 1.0), Vector2::new(9.0, 1.0), Vector2::new(9.0, 10.0)];
+	assert_eq!(ls.offset_basic(1f64), Some(mitre.clone()));
+
+	// The mitre point is sqrt(2) from the vertex, so a limit of 1 bevels, which loops back past the crossing
+	let bevel = vec![Vector2::new(0.0, 1.0), Vector2::new(10.0, 1.0), Vector2::new(9.0, 0.0), Vector2::new(9.0, 10.0)];
+	assert_eq!(ls.offset_basic_with_options(1f64, &OffsetOptions::mitre(1f64)), Some(bevel.clone()));
+	assert_eq!(ls.offset_basic_with_options(1f64, &OffsetOptions::bevel()), Some(bevel));
+
+	let round = ls.offset_basic_with_options(1f64, &OffsetOptions::round(ArcTolerance::Angle(std::f64::consts::FRAC_PI_4))).unwrap();
+	assert_eq!(round.len(), 5);
+	// the arc turns anticlockwise into the corner
+	let middle = Vector2::new(10.0 - std::f64::consts::FRAC_1_SQRT_2, std::f64::consts::FRAC_1_SQRT_2);
+	assert!(round[2].distance_to(middle) < 0.000000001);
+
+	// The loops are clipped by the full offset
+	for options in [OffsetOptions::bevel(), OffsetOptions::round(ArcTolerance::Angle(0.1))] {
+		let offset = ls.offset_with_options(1f64, &options).unwrap();
+		assert_eq!(offset.len(), 1);
+		assert_eq!(offset[0].into_tuples(), vec![(0.0, 1.0), (9.0, 1.0), (9.0, 10.0)]);
+	}
+}
+
+#[test]
+fn test_offset_basic_variable() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(1.0, 1.0),
+		Vector2::new(1.5, 2.0),
+		Vector2::new(1.0, 3.0),
+	].into();
+	assert_eq!(
+		ls.offset_basic_variable(|_| -0.5f64, &OffsetOptions::default()),
+		ls.offset_basic(-0.5f64)
+	);
+
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+	].into();
+	let taper = ls.offset_basic_breakpoints(&[(0.2, 1.0), (0.6, 3.0)], &OffsetOptions::default()).unwrap();
+	let expected = [
+		Vector2::new(0.0, 1.0),
+		Vector2::new(2.0, 1.0),
+		Vector2::new(6.0, 3.0),
+		Vector2::new(10.0, 3.0),
+	];
+	assert_eq!(taper.len(), expected.len());
+	for (actual, expected) in taper.iter().zip(expected.iter()) {
+		assert!(actual.distance_to(*expected) < 0.000000001);
+	}
+
+	// a repeated vertex is skipped, but a NaN vertex can not be offset
+	let repeated:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(5.0, 0.0), Vector2::new(5.0, 0.0), Vector2::new(10.0, 0.0)].into();
+	assert_eq!(
+		repeated.offset_basic_variable(|fraction| fraction * 2.0, &OffsetOptions::default()),
+		Some(vec![Vector2::new(0.0, 0.0), Vector2::new(5.0, 1.0), Vector2::new(10.0, 2.0)])
+	);
+	let with_nan:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(f64::NAN, 0.0), Vector2::new(10.0, 0.0)].into();
+	assert_eq!(with_nan.offset_basic_variable(|_| 1.0, &OffsetOptions::default()), None);
+	assert_eq!(with_nan.offset_basic_breakpoints(&[(0.5, 1.0)], &OffsetOptions::default()), None);
+}
+
+fn ring_area(ring: &[Vector2]) -> f64 {
+	ring.iter().zip(ring.iter().skip(1)).map(|(a, b)| a.cross(*b)).sum::<f64>() / 2f64
+}
+
+#[test]
+fn test_buffer_caps() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+	].into();
+	let flat = ls.buffer(1f64, CapStyle::Flat, &OffsetOptions::default()).unwrap();
+	assert_eq!(flat.len(), 1);
+	assert_eq!(flat[0].first(), flat[0].last());
+	assert!((ring_area(&flat[0]) - 20f64).abs() < 0.000000001);
+
+	let square = ls.buffer(1f64, CapStyle::Square, &OffsetOptions::default()).unwrap();
+	assert!((ring_area(&square[0]) - 24f64).abs() < 0.000000001);
+
+	let round = ls.buffer(1f64, CapStyle::Round(ArcTolerance::Angle(0.01)), &OffsetOptions::default()).unwrap();
+	assert!((ring_area(&round[0]) - (20f64 + std::f64::consts::PI)).abs() < 0.001);
+
+	let left = ls.buffer_one_sided(1f64, &OffsetOptions::default()).unwrap();
+	assert!((ring_area(&left[0]) - 10f64).abs() < 0.000000001);
+	assert!(left[0].iter().all(|point| point.y >= 0f64));
+	let right = ls.buffer_one_sided(-1f64, &OffsetOptions::default()).unwrap();
+	assert!((ring_area(&right[0]) - 10f64).abs() < 0.000000001);
+	assert!(right[0].iter().all(|point| point.y <= 0f64));
+}
+
+#[test]
+fn test_buffer_bends_and_holes() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+	].into();
+	// The inside corner makes a loop in the raw offset which must not end up in the polygon
+	let rings = ls.buffer(1f64, CapStyle::Flat, &OffsetOptions::default()).unwrap();
+	assert_eq!(rings.len(), 1);
+	assert!((ring_area(&rings[0]) - 40f64).abs() < 0.000000001);
+
+	// Crossing itself encloses a hole
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(5.0, 10.0),
+		Vector2::new(5.0, -5.0),
+	].into();
+	let rings = ls.buffer(1f64, CapStyle::Round(ArcTolerance::Angle(0.1)), &OffsetOptions::round(ArcTolerance::Angle(0.1))).unwrap();
+	assert_eq!(rings.len(), 2);
+	assert!(ring_area(&rings[0]) > 0f64);
+	// the hole is the 3 by 8 rectangle inside the loop
+	assert!((ring_area(&rings[1]) + 24f64).abs() < 0.000000001);
+}
+
+#[test]
+fn test_winding_numbers() {
+	// a pentagram winds twice around its middle, and the square overlaps part of it
+	let star:Vec<Vector2> = (0..5)
+		.map(|index| Vector2::new(0.0, 5.0).rotate(index as f64 * 4f64 * std::f64::consts::PI / 5f64))
+		.collect();
+	let square = vec![Vector2::new(-1.0, -1.0), Vector2::new(6.0, -1.0), Vector2::new(6.0, 6.0), Vector2::new(-1.0, 6.0)];
+	let rings = vec![star, square];
+	let points:Vec<Vector2> = (0..40)
+		.flat_map(|i| (0..40).map(move |j| Vector2::new(-7.05 + i as f64 * 0.37, -7.05 + j as f64 * 0.37)))
+		.collect();
+	let expected:Vec<i32> = points
+		.iter()
+		.map(|&point| rings.iter().map(|ring| polyline::winding_number(ring, point)).sum())
+		.collect();
+	assert!(expected.contains(&3));
+	assert_eq!(polyline::winding_numbers(&rings, &points), expected);
+}
+
+#[test]
+fn test_offset_closed_ring() {
+	let ring:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(0.0, 10.0),
+		Vector2::new(0.0, 0.0),
+	].into();
+	assert!(ring.is_closed());
+	assert_eq!(
+		ring.offset_basic(-1f64),
+		Some(vec![
+			Vector2::new(-1.0, -1.0),
+			Vector2::new(11.0, -1.0),
+			Vector2::new(11.0, 11.0),
+			Vector2::new(-1.0, 11.0),
+			Vector2::new(-1.0, -1.0),
+		])
+	);
+	let inside = ring.offset(1f64).unwrap();
+	assert_eq!(inside.len(), 1);
+	assert!(inside[0].is_closed());
+	assert!((inside[0].magnitude() - 32f64).abs() < 0.000000001);
+
+	let open:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+	].into();
+	assert!(!open.is_closed());
+	assert!(open.closed().is_closed());
+	assert_eq!(open.closed().segments.len(), 3);
+}
+
+#[test]
+fn test_offset_ring_and_polygon() {
+	// anticlockwise and clockwise rings offset the same way
+	let ring:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(0.0, 10.0),
+	].into();
+	let reversed:LineStringMeasured = vec![
+		Vector2::new(0.0, 10.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(0.0, 0.0),
+	].into();
+	for ring in &[ring, reversed] {
+		let outward = ring.offset_ring(1f64, &OffsetOptions::default());
+		assert_eq!(outward.len(), 1);
+		assert!((ring_area(&outward[0][0]) - 144f64).abs() < 0.000000001);
+		let inward = ring.offset_ring(-2f64, &OffsetOptions::default());
+		assert_eq!(inward.len(), 1);
+		assert!((ring_area(&inward[0][0]) - 36f64).abs() < 0.000000001);
+		assert_eq!(ring.offset_ring(-6f64, &OffsetOptions::default()), Vec::<Vec<Vec<Vector2>>>::new());
+	}
+
+	// two squares joined by a narrow neck split apart when offset inwards
+	let dumbbell:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 4.0),
+		Vector2::new(20.0, 4.0),
+		Vector2::new(20.0, 0.0),
+		Vector2::new(30.0, 0.0),
+		Vector2::new(30.0, 10.0),
+		Vector2::new(20.0, 10.0),
+		Vector2::new(20.0, 6.0),
+		Vector2::new(10.0, 6.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(0.0, 10.0),
+	].into();
+	let split = dumbbell.offset_ring(-1.5f64, &OffsetOptions::default());
+	assert_eq!(split.len(), 2);
+	for polygon in &split {
+		assert_eq!(polygon.len(), 1);
+		assert!((ring_area(&polygon[0]) - 49f64).abs() < 0.000000001);
+	}
+
+	// the hole grows as the polygon shrinks
+	let hole:LineStringMeasured = vec![
+		Vector2::new(4.0, 4.0),
+		Vector2::new(6.0, 4.0),
+		Vector2::new(6.0, 6.0),
+		Vector2::new(4.0, 6.0),
+	].into();
+	let exterior:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+		Vector2::new(0.0, 10.0),
+	].into();
+	let shrunk = LineStringMeasured::offset_polygon(&[exterior, hole], -1f64, &OffsetOptions::default());
+	assert_eq!(shrunk.len(), 1);
+	assert_eq!(shrunk[0].len(), 2);
+	assert!((ring_area(&shrunk[0][0]) - 64f64).abs() < 0.000000001);
+	assert!((ring_area(&shrunk[0][1]) + 16f64).abs() < 0.000000001);
+}
+
+#[test]
+fn test_locate() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(10.0, 0.0),
+		Vector2::new(10.0, 10.0),
+	].into();
+	assert_eq!(
+		ls.locate(Vector2::new(4.0, 3.0)),
+		Some(LocatedPoint {
+			point: Vector2::new(4.0, 0.0),
+			fraction_of_length: 0.2,
+			distance_along: 4.0,
+			distance_from_line: 3.0,
+			offset: 3.0,
+			side: Side::Left,
+		})
+	);
+	let located = ls.locate(Vector2::new(12.0, 5.0)).unwrap();
+	assert_eq!(located.point, Vector2::new(10.0, 5.0));
+	assert_eq!(located.distance_along, 15.0);
+	assert_eq!(located.side, Side::Right);
+	assert_eq!(located.signed_distance_from_line(), -2.0);
+	// the located point is on the offset which passes through the original point
+	let offset = LineStringMeasured::from(ls.offset_basic(located.signed_distance_from_line()).unwrap());
+	assert_eq!(offset.locate(Vector2::new(12.0, 5.0)).unwrap().distance_from_line, 0.0);
+
+	// beyond the end
+	let located = ls.locate(Vector2::new(-3.0, -4.0)).unwrap();
+	assert_eq!(located.point, Vector2::new(0.0, 0.0));
+	assert_eq!(located.fraction_of_length, 0.0);
+	assert_eq!(located.distance_from_line, 5.0);
+	assert_eq!(located.offset, -4.0);
+	assert_eq!(located.side, Side::Right);
+
+	assert_eq!(ls.locate(Vector2::new(10.0, 2.0)).unwrap().side, Side::On);
+	assert_eq!(ls.locate(Vector2::new(10.0, 2.0 + 1e-15)).unwrap().side, Side::On);
+	// in line with a segment but off the linestring, so the side comes from the nearest segment
+	let located = ls.locate(Vector2::new(15.0, 0.0)).unwrap();
+	assert_eq!(located.point, Vector2::new(10.0, 0.0));
+	assert_eq!((located.side, located.offset), (Side::Right, -5.0));
+	let located = ls.locate(Vector2::new(10.0, 15.0)).unwrap();
+	assert_eq!((located.side, located.distance_from_line, located.offset), (Side::Left, 5.0, 0.0));
+	let single:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0)].into();
+	assert_eq!(single.locate(Vector2::new(15.0, 0.0)).unwrap().side, Side::Left);
+	assert_eq!(single.locate(Vector2::new(15.0, -1.0)).unwrap().side, Side::Right);
+	let empty:LineStringMeasured = vec![].into();
+	assert_eq!(empty.locate(Vector2::new(0.0, 0.0)), None);
+}
+
+#[test]
+fn test_line_string_m() {
+	// calibrated measures which don't match length, with a gap from 100 to 200 at (10, 0)
+	let ls:LineStringM = vec![
+		(0.0, 0.0, 0.0),
+		(10.0, 0.0, 100.0),
+		(10.0, 0.0, 200.0),
+		(10.0, 10.0, 300.0),
+	].into();
+	assert_eq!(ls.interpolate(50.0), Some(Vector2::new(5.0, 0.0)));
+	assert_eq!(ls.interpolate(150.0), Some(Vector2::new(10.0, 0.0)));
+	assert_eq!(ls.interpolate(250.0), Some(Vector2::new(10.0, 5.0)));
+	assert_eq!(ls.interpolate(301.0), None);
+	assert_eq!(ls.direction(250.0), Some(std::f64::consts::FRAC_PI_2));
+	assert_eq!(ls.distance_along_at_measure(250.0), Some(15.0));
+	assert_eq!(ls.measure_at_distance_along(15.0), Some(250.0));
+	assert_eq!(ls.measure_at_distance_along(10.0), Some(100.0));
+	assert_eq!(ls.locate(Vector2::new(12.0, 5.0)), Some((Vector2::new(10.0, 5.0), 250.0)));
+
+	let (before, after) = ls.cut(250.0);
+	assert_eq!(before.unwrap().into_tuples_measured(), vec![(0.0, 0.0, 0.0), (10.0, 0.0, 100.0), (10.0, 0.0, 200.0), (10.0, 5.0, 250.0)]);
+	assert_eq!(after.unwrap().into_tuples_measured(), vec![(10.0, 5.0, 250.0), (10.0, 10.0, 300.0)]);
+
+	let (before, after) = ls.cut(100.0);
+	assert_eq!(before.unwrap().into_tuples_measured(), vec![(0.0, 0.0, 0.0), (10.0, 0.0, 100.0)]);
+	assert_eq!(after.unwrap().into_tuples_measured(), vec![(10.0, 0.0, 100.0), (10.0, 0.0, 200.0), (10.0, 10.0, 300.0)]);
+
+	assert_eq!(ls.cut(-5.0), (None, Some(ls.clone())));
+	assert_eq!(ls.cut(500.0), (Some(ls.clone()), None));
+
+	assert_eq!(
+		ls.cut_twice(50.0, 250.0).unwrap().into_tuples_measured(),
+		vec![(5.0, 0.0, 50.0), (10.0, 0.0, 100.0), (10.0, 0.0, 200.0), (10.0, 5.0, 250.0)]
+	);
+	assert_eq!(
+		ls.offset_basic(250.0, 300.0, 1.0, &OffsetOptions::default()),
+		Some(vec![Vector2::new(9.0, 5.0), Vector2::new(9.0, 10.0)])
+	);
+
+	let plain:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(0.0, 2.0),
+		Vector2::new(4.0, 2.0),
+	].into();
+	let calibrated = LineStringM::from_line_string(&plain, 10.0, 22.0);
+	assert_eq!(calibrated.into_tuples_measured(), plain.into_tuples_measured(10.0, 22.0));
+	assert_eq!(LineStringM::new(vec![Vector2::new(0.0, 0.0)], vec![]), None);
+}
+
+#[test]
+fn test_cut_and_interpolate_at_distance_and_measure() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(0.1, 0.0),
+		Vector2::new(0.1, 0.7),
+		Vector2::new(1.3, 0.7),
+	].into();
+
+	let (start, end) = (0.3f64, 1.7f64);
+	let (_, middle, _) = ls.cut_twice_at_distance(start, end);
+	let middle_points:Vec<Vector2> = (&middle.unwrap()).into();
+	assert_eq!(middle_points.first().copied(), ls.interpolate_at_distance(start));
+	assert_eq!(middle_points.last().copied(), ls.interpolate_at_distance(end));
+
+	assert!(ls.interpolate_at_distance(0.4).unwrap().distance_to(Vector2::new(0.1, 0.3)) < 1e-12);
+	assert_eq!(ls.interpolate_at_distance(-1.0), Some(Vector2::new(0.0, 0.0)));
+	assert_eq!(ls.direction_at_distance(0.05), 0.0);
+	assert_eq!(ls.direction_at_distance(0.4), std::f64::consts::FRAC_PI_2);
+	assert_eq!(ls.cut_at_distance(0.1).0.unwrap().mag, 0.1);
+
+	// measured backwards from 1000 at the start to 0 at the end
+	let measure_at = |distance_along:f64| 1000.0 - 1000.0 * distance_along / ls.mag;
+	assert!((ls.distance_at_measure(measure_at(0.4), 1000.0, 0.0) - 0.4).abs() < 1e-12);
+	assert_eq!(ls.direction_at_measure(measure_at(0.4), 1000.0, 0.0), std::f64::consts::FRAC_PI_2);
+	assert!(ls.interpolate_at_measure(measure_at(0.4), 1000.0, 0.0).unwrap().distance_to(Vector2::new(0.1, 0.3)) < 1e-12);
+	let (before, after) = ls.cut_at_measure(measure_at(0.4), 1000.0, 0.0);
+	assert!((before.unwrap().mag - 0.4).abs() < 1e-12);
+	assert!((after.unwrap().mag - 1.6).abs() < 1e-12);
+	let (_, middle, _) = ls.cut_twice_at_measure(measure_at(0.3), measure_at(1.7), 1000.0, 0.0);
+	assert!((middle.unwrap().mag - 1.4).abs() < 1e-12);
+}
+
+#[test]
+fn test_substring() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(1.0, 0.0),
+		Vector2::new(1.0, 1.0),
+		Vector2::new(0.0, 1.0),
+	].into();
+
+	let forward = ls.substring(0.25, 0.75).unwrap();
+	assert_eq!(forward.into_tuples(), vec![(0.75, 0.0), (1.0, 0.0), (1.0, 1.0), (0.75, 1.0)]);
+	assert_eq!(forward.mag, 1.5);
+	assert_eq!(forward, ls.cut_twice(0.25, 0.75).1.unwrap());
+
+	let backward = ls.substring(0.75, 0.25).unwrap();
+	assert_eq!(backward.into_tuples(), vec![(0.75, 1.0), (1.0, 1.0), (1.0, 0.0), (0.75, 0.0)]);
+	assert_eq!(backward, forward.reversed());
+
+	// starting and ending on vertices does not add zero length segments
+	assert_eq!(ls.substring_at_distance(1.0, 2.0).unwrap().into_tuples(), vec![(1.0, 0.0), (1.0, 1.0)]);
+	// positions are clamped to the ends of the line
+	assert_eq!(ls.substring(-1.0, 2.0).unwrap(), ls);
+	assert_eq!(ls.substring_at_measure(30.0, 10.0, 0.0, 30.0).unwrap(), ls.substring_at_distance(3.0, 1.0).unwrap());
+
+	let point = ls.substring(0.5, 0.5).unwrap();
+	assert_eq!(point.into_tuples(), vec![(1.0, 0.5), (1.0, 0.5)]);
+	assert_eq!(point.mag, 0.0);
+
+	let empty:LineStringMeasured = vec![Vector2::new(0.0, 0.0)].into();
+	assert_eq!(empty.substring(0.0, 1.0), None);
+}
+
+#[test]
+fn test_split_at() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(2.0, 0.0),
+		Vector2::new(2.0, 2.0),
+	].into();
+
+	let pieces = ls.split_at(&[0.75, 0.25, 0.5, 0.5]);
+	assert_eq!(pieces.len(), 5);
+	assert_eq!(pieces[0].into_tuples(), vec![(0.0, 0.0), (1.0, 0.0)]);
+	assert_eq!(pieces[1].into_tuples(), vec![(1.0, 0.0), (2.0, 0.0)]);
+	assert_eq!(pieces[2].into_tuples(), vec![(2.0, 0.0), (2.0, 0.0)]);
+	assert_eq!(pieces[2].mag, 0.0);
+	assert_eq!(pieces[3].into_tuples(), vec![(2.0, 0.0), (2.0, 1.0)]);
+	assert_eq!(pieces[4].into_tuples(), vec![(2.0, 1.0), (2.0, 2.0)]);
+	assert_eq!(pieces.iter().map(|piece| piece.mag).sum::<f64>(), ls.mag);
+	assert_eq!(ls.split_at(&[]), vec![ls.clone()]);
+
+	let chunks = ls.chunks_of_length(1.5);
+	assert_eq!(chunks.iter().map(|chunk| chunk.mag).collect::<Vec<f64>>(), vec![1.5, 1.5, 1.0]);
+	assert_eq!(chunks[1].into_tuples(), vec![(1.5, 0.0), (2.0, 0.0), (2.0, 1.0)]);
+	assert_eq!(ls.chunks_of_length(1.0).len(), 4);
+	assert_eq!(ls.chunks_of_length(0.0), vec![ls.clone()]);
+}
+
+#[test]
+fn test_cumulative_index() {
+	let points:Vec<Vector2> = (0..1000).map(|index| Vector2::new(index as f64, (index % 2) as f64)).collect();
+	let ls:LineStringMeasured = points.clone().into();
+	assert_eq!(ls.cumulative().len(), ls.segments.len());
+	assert_eq!(ls.cumulative().last().copied(), Some(ls.mag));
+	assert_eq!(LineStringMeasured::from_segments(ls.segments.clone()), ls);
+
+	let (before, after) = ls.cut(0.3);
+	let (before, after) = (before.unwrap(), after.unwrap());
+	assert_eq!(before.cumulative(), LineStringMeasured::from_segments(before.segments.clone()).cumulative());
+	assert_eq!(after.cumulative(), LineStringMeasured::from_segments(after.segments.clone()).cumulative());
+	assert_eq!(after.cumulative().last().copied(), Some(after.segments.iter().map(|segment| segment.mag).sum()));
+
+	// compare the binary search against a linear scan
+	let segment_length = 2f64.sqrt();
+	for index in 0..999 {
+		let distance_along = segment_length * (index as f64 + 0.5);
+		let expected = (points[index] + points[index + 1]) / 2.0;
+		assert!(ls.interpolate_at_distance(distance_along).unwrap().distance_to(expected) < 1e-9);
+		assert_eq!(ls.direction_at_distance(distance_along), (points[index + 1] - points[index]).direction());
+	}
+}
+
+#[test]
+fn test_measure_search() {
+	// measures increasing, decreasing, and with an equation which jumps back
+	let increasing = LineStringM::from(vec![(0.0, 0.0, 0.0), (10.0, 0.0, 100.0), (10.0, 0.0, 100.0), (20.0, 0.0, 300.0)]);
+	let decreasing = LineStringM::from(vec![(0.0, 0.0, 300.0), (10.0, 0.0, 100.0), (20.0, 0.0, 0.0)]);
+	let mixed = LineStringM::from(vec![(0.0, 0.0, 0.0), (10.0, 0.0, 100.0), (10.0, 0.0, 50.0), (20.0, 0.0, 150.0)]);
+	assert_eq!(increasing.measures(), &[0.0, 100.0, 100.0, 300.0]);
+	assert_eq!(increasing.distance_along_at_measure(0.0), Some(0.0));
+	assert_eq!(increasing.distance_along_at_measure(100.0), Some(10.0));
+	assert_eq!(increasing.distance_along_at_measure(200.0), Some(15.0));
+	assert_eq!(increasing.distance_along_at_measure(300.0), Some(20.0));
+	assert_eq!(increasing.distance_along_at_measure(-1.0), None);
+	assert_eq!(increasing.distance_along_at_measure(301.0), None);
+	assert_eq!(increasing.distance_along_at_measure(f64::NAN), None);
+	assert_eq!(decreasing.distance_along_at_measure(300.0), Some(0.0));
+	assert_eq!(decreasing.distance_along_at_measure(200.0), Some(5.0));
+	assert_eq!(decreasing.distance_along_at_measure(50.0), Some(15.0));
+	assert_eq!(decreasing.distance_along_at_measure(0.0), Some(20.0));
+	assert_eq!(decreasing.distance_along_at_measure(301.0), None);
+	// the first occurrence of a repeated measure is used
+	assert_eq!(mixed.distance_along_at_measure(75.0), Some(7.5));
+	assert_eq!(mixed.distance_along_at_measure(125.0), Some(17.5));
+	assert_eq!(LineStringM::from(vec![(0.0, 0.0, 5.0)]).distance_along_at_measure(5.0), None);
+}
+
+#[test]
+fn test_batch_queries() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(3.0, 0.0),
+		Vector2::new(3.0, 4.0),
+		Vector2::new(-1.0, 4.0),
+	].into();
+
+	let sorted = [-0.5, 0.0, 0.1, 0.27, 0.3, 0.3, 0.65, 0.99, 1.0, 1.5];
+	let unsorted = [0.65, 1.5, 0.0, 0.3, -0.5, 0.99, 0.27, 1.0, 0.1, 0.3];
+	for fractions in [&sorted, &unsorted] {
+		assert_eq!(
+			ls.interpolate_many(fractions),
+			fractions.iter().map(|&fraction| ls.interpolate(fraction)).collect::<Vec<Option<Vector2>>>()
+		);
+		assert_eq!(
+			ls.direction_many(fractions),
+			fractions.iter().map(|&fraction| ls.direction(fraction)).collect::<Vec<f64>>()
+		);
+	}
+
+	let points = [Vector2::new(1.0, 1.0), Vector2::new(5.0, 5.0), Vector2::new(3.0, 2.0)];
+	assert_eq!(
+		ls.locate_many(&points),
+		points.iter().map(|&point| ls.locate(point)).collect::<Vec<Option<LocatedPoint>>>()
+	);
+
+	let empty:LineStringMeasured = vec![].into();
+	assert_eq!(empty.interpolate_many(&sorted), vec![None; sorted.len()]);
+}
+
+#[test]
+fn test_errors() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(2.0, 0.0),
+		Vector2::new(2.0, 0.0),
+		Vector2::new(2.0, 2.0),
+	].into();
+	let empty:LineStringMeasured = vec![Vector2::new(1.0, 1.0)].into();
+	let zero_length:LineStringMeasured = vec![Vector2::new(1.0, 1.0), Vector2::new(1.0, 1.0)].into();
+	let non_finite:LineStringMeasured = vec![Vector2::new(1.0, 1.0), Vector2::new(f64::NAN, 1.0)].into();
+
+	assert_eq!(ls.check(), Ok(()));
+	assert_eq!(empty.check(), Err(LineStringError::EmptyLine));
+	assert_eq!(zero_length.check(), Err(LineStringError::ZeroLength));
+	assert_eq!(non_finite.check(), Err(LineStringError::NonFinite));
+
+	assert_eq!(ls.try_interpolate(0.25), Ok(Vector2::new(1.0, 0.0)));
+	assert_eq!(empty.try_interpolate(0.25), Err(LineStringError::EmptyLine));
+	assert_eq!(ls.try_interpolate(1.5), Err(LineStringError::OutOfRange { value: 1.5, min: 0.0, max: 1.0 }));
+	assert_eq!(ls.try_interpolate_at_distance(-1.0), Err(LineStringError::OutOfRange { value: -1.0, min: 0.0, max: 4.0 }));
+	assert_eq!(ls.try_cut(f64::NAN), Err(LineStringError::NonFinite));
+	assert_eq!(ls.try_cut(0.5), Ok(ls.cut(0.5)));
+	assert_eq!(ls.try_substring(0.75, 0.25), Ok(ls.substring(0.75, 0.25).unwrap()));
+
+	assert_eq!(ls.try_direction(0.75), Ok(std::f64::consts::FRAC_PI_2));
+	assert_eq!(zero_length.try_direction(0.5), Err(LineStringError::ZeroLength));
+	assert_eq!(ls.try_locate(Vector2::new(f64::INFINITY, 0.0)).unwrap_err(), LineStringError::NonFinite);
+
+	assert_eq!(ls.try_offset_basic(1.0, &OffsetOptions::default()), Err(LineStringError::DegenerateSegment { index: 1 }));
+	assert_eq!(ls.try_offset(f64::NAN, &OffsetOptions::default()), Err(LineStringError::NonFinite));
+	let simple:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0)].into();
+	assert_eq!(simple.try_offset_basic(1.0, &OffsetOptions::default()), Ok(vec![Vector2::new(0.0, 1.0), Vector2::new(2.0, 1.0)]));
+
+	assert_eq!(
+		LineStringM::try_new(vec![Vector2::new(0.0, 0.0)], vec![]),
+		Err(LineStringError::MeasureCountMismatch { points: 1, measures: 0 })
+	);
+	assert_eq!(LineStringError::DegenerateSegment { index: 1 }.to_string(), "segment 1 has zero length");
+}
+
+#[test]
+fn test_validate_and_clean() {
+	let ls:LineStringMeasured = vec![
+		Vector2::new(0.0, 0.0),
+		Vector2::new(0.0, 0.0),
+		Vector2::new(2.0, 0.0),
+		Vector2::new(1.0, 0.0),
+		Vector2::new(1.0, 0.0000001),
+		Vector2::new(1.0, 2.0),
+	].into();
+	assert_eq!(ls.validate(0.0, 0.0), ValidationReport {
+		non_finite: vec![],
+		duplicates: vec![1],
+		spikes: vec![2],
+		too_few_points: false,
+	});
+	assert_eq!(ls.validate(0.001, 0.0), ValidationReport {
+		non_finite: vec![],
+		duplicates: vec![1, 4],
+		spikes: vec![2],
+		too_few_points: false,
+	});
+	assert!(!ls.validate(0.0, 0.0).is_valid());
+
+	let cleaned = ls.clean(0.001).unwrap();
+	assert_eq!(cleaned.into_tuples(), vec![(0.0, 0.0), (2.0, 0.0), (1.0, 0.0), (1.0, 2.0)]);
+	assert!(cleaned.segments.iter().all(|segment| segment.mag > 0.0));
+	assert!(cleaned.validate(0.001, 0.0).is_valid());
+	assert_eq!(cleaned.validate(0.001, 0.0).spikes, vec![1]);
+
+	// a spike which does not quite turn straight back is only found with an angle tolerance
+	let near_spike:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0), Vector2::new(1.0, 0.01), Vector2::new(1.0, 2.0)].into();
+	assert_eq!(near_spike.validate(0.0, 0.0).spikes, Vec::<usize>::new());
+	assert_eq!(near_spike.validate(0.0, 0.001).spikes, Vec::<usize>::new());
+	assert_eq!(near_spike.validate(0.0, 0.02).spikes, vec![1]);
+	assert_eq!(near_spike.validate(0.0, 1.0).spikes, vec![1]);
+
+	let with_nan:LineStringMeasured = vec![Vector2::new(0.0, 0.0), Vector2::new(f64::NAN, 0.0), Vector2::new(1.0, 0.0)].into();
+	assert_eq!(with_nan.validate(0.0, 0.0).non_finite, vec![1]);
+	assert_eq!(with_nan.clean(0.0), Err(LineStringError::NonFinite));
+
+	let point:LineStringMeasured = vec![Vector2::new(1.0, 1.0), Vector2::new(1.0, 1.0)].into();
+	assert!(point.validate(0.0, 0.0).too_few_points);
+	assert_eq!(point.clean(0.0), Err(LineStringError::EmptyLine));
+}
+
+#[test]
+fn test_multi_line_string_measured() {
+	let parts:Vec<LineStringMeasured> = vec![
+		vec![Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0)].into(),
+		vec![Vector2::new(4.0, 3.0), Vector2::new(4.0, 5.0), Vector2::new(8.0, 5.0)].into(),
+	];
+	let counted = MultiLineStringMeasured::new(parts.clone(), Gaps::Counted);
+	let not_counted = MultiLineStringMeasured::new(parts.clone(), Gaps::NotCounted);
+	assert_eq!(counted.part_starts(), &[0.0, 7.0]);
+	assert_eq!(counted.mag, 13.0);
+	assert_eq!(not_counted.part_starts(), &[0.0, 4.0]);
+	assert_eq!(not_counted.mag, 10.0);
+
+	assert_eq!(counted.interpolate_at_distance(2.0), Some(Vector2::new(2.0, 0.0)));
+	assert_eq!(counted.interpolate_at_distance(5.5), Some(Vector2::new(4.0, 1.5)));
+	assert_eq!(counted.interpolate_at_distance(8.0), Some(Vector2::new(4.0, 4.0)));
+	assert_eq!(not_counted.interpolate_at_distance(5.0), Some(Vector2::new(4.0, 4.0)));
+	assert_eq!(not_counted.interpolate(1.0), Some(Vector2::new(8.0, 5.0)));
+
+	let located = counted.locate(Vector2::new(6.0, 6.0)).unwrap();
+	assert_eq!(located.point, Vector2::new(6.0, 5.0));
+	assert_eq!(located.distance_along, 11.0);
+	assert_eq!(located.side, Side::Left);
+	assert_eq!(not_counted.locate(Vector2::new(6.0, 6.0)).unwrap().distance_along, 8.0);
+
+	// a substring spanning both parts keeps the gap
+	let middle = counted.substring_at_distance(2.0, 8.0).unwrap();
+	assert_eq!(middle.parts().len(), 2);
+	assert_eq!(middle.parts[0].into_tuples(), vec![(2.0, 0.0), (4.0, 0.0)]);
+	assert_eq!(middle.parts[1].into_tuples(), vec![(4.0, 3.0), (4.0, 4.0)]);
+	assert_eq!(middle.mag, 6.0);
+	let backwards = counted.substring_at_distance(8.0, 2.0).unwrap();
+	assert_eq!(backwards, middle.reversed());
+	assert_eq!(backwards.parts[0].into_tuples(), vec![(4.0, 4.0), (4.0, 3.0)]);
+	assert_eq!(counted.substring_at_distance(5.0, 6.0), None);
+	assert_eq!(counted.substring_at_distance(3.0, 3.0).unwrap().parts[0].into_tuples(), vec![(3.0, 0.0), (3.0, 0.0)]);
+
+	let (before, after) = not_counted.cut_at_distance(5.0);
+	assert_eq!(before.unwrap().mag, 5.0);
+	let after = after.unwrap();
+	assert_eq!(after.parts.len(), 1);
+	assert_eq!(after.parts[0].into_tuples(), vec![(4.0, 4.0), (4.0, 5.0), (8.0, 5.0)]);
+	assert_eq!(not_counted.cut(0.0), (None, Some(not_counted.clone())));
+
+	// a cut inside a counted gap splits the gap between the two sides
+	let (before, after) = counted.cut_at_distance(5.0);
+	let (before, after) = (before.unwrap(), after.unwrap());
+	assert_eq!(before.mag, 5.0);
+	assert_eq!(after.mag, 8.0);
+	assert_eq!(before.parts.last().unwrap().into_tuples(), vec![(4.0, 1.0), (4.0, 1.0)]);
+	assert_eq!(after.parts[0].into_tuples(), vec![(4.0, 1.0), (4.0, 1.0)]);
+	assert_eq!(after.interpolate_at_distance(1.0), Some(Vector2::new(4.0, 2.0)));
+	assert_eq!(after.interpolate_at_distance(3.0), Some(Vector2::new(4.0, 4.0)));
+	assert_eq!(counted.substring_at_distance(8.0, 5.0).unwrap().mag, 3.0);
+	assert_eq!(counted.substring_at_distance(5.0, 6.0), None);
+
+	let with_nan = MultiLineStringMeasured::new(vec![vec![Vector2::new(f64::NAN, 0.0), Vector2::new(1.0, 0.0)].into(), parts[1].clone()], Gaps::NotCounted);
+	assert_eq!(with_nan.locate(Vector2::new(6.0, 6.0)).unwrap().point, Vector2::new(6.0, 5.0));
+
+	let offset = counted.offset(1.0);
+	assert_eq!(offset.len(), 2);
+	assert_eq!(offset[0].into_tuples(), vec![(0.0, 1.0), (4.0, 1.0)]);
+	assert_eq!(offset[1].into_tuples(), vec![(3.0, 3.0), (3.0, 6.0), (8.0, 6.0)]);
+}
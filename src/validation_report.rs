@@ -0,0 +1,20 @@
+/// Problems found in the points of a linestring by `LineStringMeasured::validate`.
+/// Each list holds point indices, counting from zero at the start of the linestring.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+	/// Points with a NaN or infinite coordinate
+	pub non_finite: Vec<usize>,
+	/// Points which are within the tolerance of the point before them, making a zero length segment
+	pub duplicates: Vec<usize>,
+	/// Points where the linestring turns back on itself, to within the spike angle
+	pub spikes: Vec<usize>,
+	/// True if there are fewer than two distinct points
+	pub too_few_points: bool,
+}
+
+impl ValidationReport {
+	/// True if no problems were found. Spikes are allowed, since they are sometimes intended.
+	pub fn is_valid(&self) -> bool {
+		self.non_finite.is_empty() && self.duplicates.is_empty() && !self.too_few_points
+	}
+}
@@ -15,6 +15,7 @@ impl Serialize for Vector2 {
 	}
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<(f64,f64)> for &Vector2 {
 	fn into(self)->(f64,f64){
 		(self.x, self.y)
@@ -62,6 +63,15 @@ impl Vector2 {
 		let mag = self.magnitude();
 		Vector2::new(self.x / mag, self.y / mag)
 	}
+	/// True if neither coordinate is NaN or infinite
+	pub fn is_finite(&self) -> bool {
+		self.x.is_finite() && self.y.is_finite()
+	}
+	/// Rotates anticlockwise by an angle in radians
+	pub fn rotate(&self, radians: f64) -> Self {
+		let (sin, cos) = radians.sin_cos();
+		Vector2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+	}
 }
 
 impl ops::Add for Vector2 {
@@ -75,6 +85,7 @@ impl ops::Add for Vector2 {
 }
 impl<'a> ops::Add<&'a Vector2> for &Vector2 {
 	type Output = Vector2;
+	#[allow(clippy::op_ref)]
 	fn add(self, other: &'a Vector2) -> Vector2 {
 		Vector2 {
 			x: self.x + &other.x,
@@ -94,6 +105,7 @@ impl ops::Sub for Vector2 {
 }
 impl<'a> ops::Sub<&'a Vector2> for &Vector2 {
 	type Output = Vector2;
+	#[allow(clippy::op_ref)]
 	fn sub(self, other: &'a Vector2) -> Vector2 {
 		Vector2 {
 			x: self.x - &other.x,
@@ -171,6 +183,7 @@ mod tests {
 		assert_ne!(v1, v3);
 	}
 	#[test]
+	#[allow(clippy::clone_on_copy)]
 	fn clone_derived() {
 		let v1 = Vector2::new(1.0, 2.0);
 		let v2 = v1.clone();
@@ -259,4 +272,18 @@ mod tests {
 		assert_eq!(v3.x, 3.0);
 		assert_eq!(v3.y, 4.0);
 	}
+	#[test]
+	fn rotate() {
+		let v1 = Vector2::new(3.0, 4.0);
+		let v2 = v1.rotate(std::f64::consts::FRAC_PI_2);
+		assert!((v2 - v1.left()).magnitude() < 0.000000001);
+		let v3 = v1.rotate(-std::f64::consts::FRAC_PI_2);
+		assert!((v3 - v1.right()).magnitude() < 0.000000001);
+	}
+	#[test]
+	fn is_finite() {
+		assert!(Vector2::new(3.0, 4.0).is_finite());
+		assert!(!Vector2::new(f64::NAN, 4.0).is_finite());
+		assert!(!Vector2::new(3.0, f64::NEG_INFINITY).is_finite());
+	}
 }
\ No newline at end of file
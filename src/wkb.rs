@@ -0,0 +1,329 @@
+use std::convert::TryInto;
+use std::fmt;
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::line_string_m::LineStringM;
+
+const LINE_STRING: u32 = 2;
+const MULTI_LINE_STRING: u32 = 5;
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// Which kind of WKB to write. Both are always written little endian, and both can be read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WkbDialect {
+	/// ISO / OGC WKB, where M geometries have type codes 2002 and 2005
+	Iso,
+	/// PostGIS extended WKB, with Z, M and SRID flags in the high bits of the type code
+	Extended { srid: Option<u32> },
+}
+
+/// The reason some WKB could not be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WkbError {
+	/// The bytes ended before the geometry was complete
+	UnexpectedEnd,
+	/// The byte order marker was not 0 or 1
+	InvalidByteOrder(u8),
+	/// The geometry type code is not one which can be read into the requested type
+	UnsupportedGeometry(u32),
+	/// A measured type was requested, but the geometry has no M values
+	MissingMeasures,
+	/// There were bytes left over after the geometry
+	TrailingBytes(usize),
+}
+
+impl fmt::Display for WkbError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WkbError::UnexpectedEnd => write!(f, "unexpected end of WKB"),
+			WkbError::InvalidByteOrder(byte) => write!(f, "invalid WKB byte order {}", byte),
+			WkbError::UnsupportedGeometry(code) => write!(f, "unsupported WKB geometry type {}", code),
+			WkbError::MissingMeasures => write!(f, "the WKB geometry has no M values"),
+			WkbError::TrailingBytes(count) => write!(f, "{} bytes left over after the WKB geometry", count),
+		}
+	}
+}
+
+impl std::error::Error for WkbError {}
+
+impl LineStringMeasured {
+	/// Writes a 2D linestring.
+	pub fn to_wkb(&self, dialect: WkbDialect) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_header(&mut bytes, LINE_STRING, false, dialect, true);
+		let points: Vec<Vector2> = self.into();
+		write_points(&mut bytes, &points.iter().map(|point| vec![point.x, point.y]).collect::<Vec<Vec<f64>>>());
+		bytes
+	}
+
+	/// Reads a linestring, with or without Z and M values, which are ignored. Any SRID is ignored; use `wkb::srid` to get it.
+	pub fn from_wkb(bytes: &[u8]) -> Result<LineStringMeasured, WkbError> {
+		let geometry = read(bytes, LINE_STRING)?;
+		Ok(to_vector2(&geometry.parts[0]).into())
+	}
+}
+
+impl LineStringM {
+	/// Writes a linestring with M values.
+	pub fn to_wkb(&self, dialect: WkbDialect) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_header(&mut bytes, LINE_STRING, true, dialect, true);
+		write_points(&mut bytes, &self.into_tuples_measured().iter().map(|&(x, y, m)| vec![x, y, m]).collect::<Vec<Vec<f64>>>());
+		bytes
+	}
+
+	/// Reads a linestring with M values. Z values are ignored.
+	pub fn from_wkb(bytes: &[u8]) -> Result<LineStringM, WkbError> {
+		let geometry = read(bytes, LINE_STRING)?;
+		to_line_string_m(&geometry, &geometry.parts[0])
+	}
+}
+
+/// Writes a 2D multilinestring.
+pub fn write_multi_line_string(lines: &[LineStringMeasured], dialect: WkbDialect) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	write_header(&mut bytes, MULTI_LINE_STRING, false, dialect, true);
+	bytes.extend_from_slice(&(lines.len() as u32).to_le_bytes());
+	for line in lines {
+		write_header(&mut bytes, LINE_STRING, false, dialect, false);
+		let points: Vec<Vector2> = line.into();
+		write_points(&mut bytes, &points.iter().map(|point| vec![point.x, point.y]).collect::<Vec<Vec<f64>>>());
+	}
+	bytes
+}
+
+/// Writes a multilinestring with M values.
+pub fn write_multi_line_string_m(lines: &[LineStringM], dialect: WkbDialect) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	write_header(&mut bytes, MULTI_LINE_STRING, true, dialect, true);
+	bytes.extend_from_slice(&(lines.len() as u32).to_le_bytes());
+	for line in lines {
+		write_header(&mut bytes, LINE_STRING, true, dialect, false);
+		write_points(&mut bytes, &line.into_tuples_measured().iter().map(|&(x, y, m)| vec![x, y, m]).collect::<Vec<Vec<f64>>>());
+	}
+	bytes
+}
+
+/// Reads a multilinestring, with or without Z and M values, which are ignored.
+pub fn read_multi_line_string(bytes: &[u8]) -> Result<Vec<LineStringMeasured>, WkbError> {
+	let geometry = read(bytes, MULTI_LINE_STRING)?;
+	Ok(geometry.parts.iter().map(|part| to_vector2(part).into()).collect())
+}
+
+/// Reads a multilinestring with M values. Z values are ignored.
+pub fn read_multi_line_string_m(bytes: &[u8]) -> Result<Vec<LineStringM>, WkbError> {
+	let geometry = read(bytes, MULTI_LINE_STRING)?;
+	geometry.parts.iter().map(|part| to_line_string_m(&geometry, part)).collect()
+}
+
+/// Returns the SRID of an EWKB geometry, or `None` if it has no SRID.
+pub fn srid(bytes: &[u8]) -> Result<Option<u32>, WkbError> {
+	let mut reader = Reader { bytes, position: 0, little_endian: true };
+	Ok(reader.header()?.srid)
+}
+
+fn write_header(bytes: &mut Vec<u8>, geometry_type: u32, has_m: bool, dialect: WkbDialect, top_level: bool) {
+	bytes.push(1);
+	let type_code = match dialect {
+		WkbDialect::Iso => geometry_type + if has_m { 2000 } else { 0 },
+		WkbDialect::Extended { srid } => {
+			let srid = srid.filter(|_| top_level);
+			let mut type_code = geometry_type;
+			if has_m {
+				type_code |= EWKB_M;
+			}
+			if srid.is_some() {
+				type_code |= EWKB_SRID;
+			}
+			bytes.extend_from_slice(&type_code.to_le_bytes());
+			if let Some(srid) = srid {
+				bytes.extend_from_slice(&srid.to_le_bytes());
+			}
+			return;
+		}
+	};
+	bytes.extend_from_slice(&type_code.to_le_bytes());
+}
+
+fn write_points(bytes: &mut Vec<u8>, points: &[Vec<f64>]) {
+	bytes.extend_from_slice(&(points.len() as u32).to_le_bytes());
+	for value in points.iter().flatten() {
+		bytes.extend_from_slice(&value.to_le_bytes());
+	}
+}
+
+fn to_vector2(part: &[Vec<f64>]) -> Vec<Vector2> {
+	part.iter().map(|point| Vector2::new(point[0], point[1])).collect()
+}
+
+fn to_line_string_m(geometry: &Geometry, part: &[Vec<f64>]) -> Result<LineStringM, WkbError> {
+	if !geometry.has_m {
+		return Err(WkbError::MissingMeasures);
+	}
+	Ok(LineStringM::from_parts(to_vector2(part).into(), part.iter().map(|point| point[point.len() - 1]).collect()))
+}
+
+struct Header {
+	geometry_type: u32,
+	has_z: bool,
+	has_m: bool,
+	srid: Option<u32>,
+}
+
+struct Geometry {
+	has_m: bool,
+	/// One list of points per linestring. Each point is `x y [z] [m]`.
+	parts: Vec<Vec<Vec<f64>>>,
+}
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+	position: usize,
+	little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, count: usize) -> Result<&'a [u8], WkbError> {
+		let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len()).ok_or(WkbError::UnexpectedEnd)?;
+		let taken = &self.bytes[self.position..end];
+		self.position = end;
+		Ok(taken)
+	}
+
+	fn u32(&mut self) -> Result<u32, WkbError> {
+		let bytes: [u8; 4] = self.take(4)?.try_into().unwrap();
+		Ok(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+	}
+
+	fn f64(&mut self) -> Result<f64, WkbError> {
+		let bytes: [u8; 8] = self.take(8)?.try_into().unwrap();
+		Ok(if self.little_endian { f64::from_le_bytes(bytes) } else { f64::from_be_bytes(bytes) })
+	}
+
+	/// Reads the byte order, type code and SRID, understanding both ISO and extended type codes.
+	fn header(&mut self) -> Result<Header, WkbError> {
+		self.little_endian = match self.take(1)?[0] {
+			0 => false,
+			1 => true,
+			other => return Err(WkbError::InvalidByteOrder(other)),
+		};
+		let type_code = self.u32()?;
+		let srid = if type_code & EWKB_SRID != 0 { Some(self.u32()?) } else { None };
+		let iso_code = type_code & 0x0FFF_FFFF;
+		Ok(Header {
+			geometry_type: iso_code % 1000,
+			has_z: type_code & EWKB_Z != 0 || iso_code / 1000 == 1 || iso_code / 1000 == 3,
+			has_m: type_code & EWKB_M != 0 || iso_code / 1000 == 2 || iso_code / 1000 == 3,
+			srid,
+		})
+	}
+
+	fn points(&mut self, dimension: usize) -> Result<Vec<Vec<f64>>, WkbError> {
+		let count = self.u32()? as usize;
+		// don't trust the count to size the allocation until the bytes are known to be there
+		if count.saturating_mul(dimension * 8) > self.bytes.len() - self.position {
+			return Err(WkbError::UnexpectedEnd);
+		}
+		(0..count).map(|_| (0..dimension).map(|_| self.f64()).collect()).collect()
+	}
+}
+
+fn read(bytes: &[u8], geometry_type: u32) -> Result<Geometry, WkbError> {
+	let mut reader = Reader { bytes, position: 0, little_endian: true };
+	let header = reader.header()?;
+	if header.geometry_type != geometry_type {
+		return Err(WkbError::UnsupportedGeometry(header.geometry_type));
+	}
+	let dimension = |header: &Header| 2 + header.has_z as usize + header.has_m as usize;
+	let parts = if geometry_type == MULTI_LINE_STRING {
+		let count = reader.u32()?;
+		let mut parts = Vec::new();
+		for _ in 0..count {
+			let part_header = reader.header()?;
+			if part_header.geometry_type != LINE_STRING {
+				return Err(WkbError::UnsupportedGeometry(part_header.geometry_type));
+			}
+			parts.push(reader.points(dimension(&part_header))?);
+		}
+		parts
+	} else {
+		vec![reader.points(dimension(&header))?]
+	};
+	if reader.position != bytes.len() {
+		return Err(WkbError::TrailingBytes(bytes.len() - reader.position));
+	}
+	Ok(Geometry { has_m: header.has_m, parts })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn from_hex(hex: &str) -> Vec<u8> {
+		(0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap()).collect()
+	}
+
+	#[test]
+	fn line_string() {
+		let ls: LineStringMeasured = vec![Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)].into();
+		// SELECT ST_AsBinary('LINESTRING(1 2, 3 4)'::geometry)
+		let wkb = from_hex("010200000002000000000000000000f03f000000000000004000000000000008400000000000001040");
+		assert_eq!(ls.to_wkb(WkbDialect::Iso), wkb);
+		assert_eq!(LineStringMeasured::from_wkb(&wkb), Ok(ls.clone()));
+
+		// SELECT ST_AsEWKB('SRID=4326;LINESTRING(1 2, 3 4)'::geometry)
+		let ewkb = from_hex("0102000020e610000002000000000000000000f03f000000000000004000000000000008400000000000001040");
+		assert_eq!(ls.to_wkb(WkbDialect::Extended { srid: Some(4326) }), ewkb);
+		assert_eq!(LineStringMeasured::from_wkb(&ewkb), Ok(ls.clone()));
+		assert_eq!(srid(&ewkb), Ok(Some(4326)));
+		assert_eq!(srid(&wkb), Ok(None));
+
+		// big endian, Z
+		let big_endian_z = from_hex("00000003ea000000023ff000000000000040000000000000004014000000000000400800000000000040100000000000004014000000000000");
+		assert_eq!(LineStringMeasured::from_wkb(&big_endian_z).unwrap().into_tuples(), vec![(1.0, 2.0), (3.0, 4.0)]);
+	}
+
+	#[test]
+	fn measured() {
+		let ls: LineStringM = vec![(1.0, 2.0, 10.0), (3.0, 4.0, 20.0)].into();
+		for dialect in [WkbDialect::Iso, WkbDialect::Extended { srid: None }, WkbDialect::Extended { srid: Some(28350) }] {
+			let bytes = ls.to_wkb(dialect);
+			assert_eq!(LineStringM::from_wkb(&bytes), Ok(ls.clone()));
+			assert_eq!(LineStringMeasured::from_wkb(&bytes), Ok(ls.line().clone()));
+		}
+		assert_eq!(&ls.to_wkb(WkbDialect::Iso)[1..5], &2002u32.to_le_bytes());
+		assert_eq!(&ls.to_wkb(WkbDialect::Extended { srid: None })[1..5], &0x4000_0002u32.to_le_bytes());
+		assert_eq!(LineStringM::from_wkb(&ls.line().to_wkb(WkbDialect::Iso)), Err(WkbError::MissingMeasures));
+	}
+
+	#[test]
+	fn multi_line_string() {
+		let lines: Vec<LineStringMeasured> = vec![
+			vec![Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)].into(),
+			vec![Vector2::new(5.0, 5.0), Vector2::new(6.0, 6.0), Vector2::new(7.0, 5.0)].into(),
+		];
+		let bytes = write_multi_line_string(&lines, WkbDialect::Extended { srid: Some(4326) });
+		assert_eq!(read_multi_line_string(&bytes), Ok(lines.clone()));
+		assert_eq!(srid(&bytes), Ok(Some(4326)));
+
+		let lines_m: Vec<LineStringM> = lines.iter().map(|line| LineStringM::from_line_string(line, 0.0, 1.0)).collect();
+		let bytes = write_multi_line_string_m(&lines_m, WkbDialect::Iso);
+		assert_eq!(read_multi_line_string_m(&bytes), Ok(lines_m));
+		assert_eq!(LineStringMeasured::from_wkb(&bytes), Err(WkbError::UnsupportedGeometry(MULTI_LINE_STRING)));
+	}
+
+	#[test]
+	fn errors() {
+		let ls: LineStringMeasured = vec![Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)].into();
+		let mut bytes = ls.to_wkb(WkbDialect::Iso);
+		assert_eq!(LineStringMeasured::from_wkb(&bytes[..bytes.len() - 1]), Err(WkbError::UnexpectedEnd));
+		bytes.push(0);
+		assert_eq!(LineStringMeasured::from_wkb(&bytes), Err(WkbError::TrailingBytes(1)));
+		bytes[0] = 7;
+		assert_eq!(LineStringMeasured::from_wkb(&bytes), Err(WkbError::InvalidByteOrder(7)));
+		assert_eq!(LineStringMeasured::from_wkb(&[1, 2, 0, 0, 0, 255, 255, 255, 255]), Err(WkbError::UnexpectedEnd));
+	}
+}
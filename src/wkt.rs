@@ -0,0 +1,362 @@
+use std::fmt;
+
+use crate::vector2::Vector2;
+use crate::line_string_measured::LineStringMeasured;
+use crate::line_string_m::LineStringM;
+
+/// The reason some WKT could not be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WktError {
+	/// The text ended before the geometry was complete
+	UnexpectedEnd,
+	/// Something other than what was expected was found
+	UnexpectedToken(String),
+	/// The geometry type is not one which can be read into the requested type
+	UnsupportedGeometry(String),
+	/// A point has the wrong number of coordinates for the dimension of the geometry
+	WrongDimension { expected: usize, found: usize },
+	/// A measured type was requested, but the geometry has no M values
+	MissingMeasures,
+}
+
+impl fmt::Display for WktError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WktError::UnexpectedEnd => write!(f, "unexpected end of WKT"),
+			WktError::UnexpectedToken(token) => write!(f, "unexpected '{}' in WKT", token),
+			WktError::UnsupportedGeometry(name) => write!(f, "unsupported WKT geometry type {}", name),
+			WktError::WrongDimension { expected, found } => write!(f, "expected {} coordinates per point but found {}", expected, found),
+			WktError::MissingMeasures => write!(f, "the WKT geometry has no M values"),
+		}
+	}
+}
+
+impl std::error::Error for WktError {}
+
+impl LineStringMeasured {
+	/// Reads a `LINESTRING`, with or without Z and M values, which are ignored.
+	pub fn from_wkt(wkt: &str) -> Result<LineStringMeasured, WktError> {
+		let geometry = parse(wkt, "LINESTRING")?;
+		Ok(to_vector2(&geometry.parts[0]).into())
+	}
+
+	/// Writes a 2D `LINESTRING`.
+	pub fn to_wkt(&self) -> String {
+		let points: Vec<Vector2> = self.into();
+		format!("LINESTRING {}", coordinates(points.iter().map(|point| vec![point.x, point.y])))
+	}
+
+	/// Writes a `LINESTRING M`, with measures from `from_measure` to `to_measure` as in `into_tuples_measured`.
+	pub fn to_wkt_measured(&self, from_measure: f64, to_measure: f64) -> String {
+		write_measured(&self.into_tuples_measured(from_measure, to_measure))
+	}
+}
+
+impl LineStringM {
+	/// Reads a `LINESTRING M` or `LINESTRING ZM`. Z values are ignored.
+	pub fn from_wkt(wkt: &str) -> Result<LineStringM, WktError> {
+		let geometry = parse(wkt, "LINESTRING")?;
+		to_line_string_m(&geometry, &geometry.parts[0])
+	}
+
+	/// Writes a `LINESTRING M`.
+	pub fn to_wkt(&self) -> String {
+		write_measured(&self.into_tuples_measured())
+	}
+}
+
+/// Reads a `MULTILINESTRING`, with or without Z and M values, which are ignored.
+pub fn read_multi_line_string(wkt: &str) -> Result<Vec<LineStringMeasured>, WktError> {
+	let geometry = parse(wkt, "MULTILINESTRING")?;
+	Ok(geometry.parts.iter().map(|part| to_vector2(part).into()).collect())
+}
+
+/// Reads a `MULTILINESTRING M` or `MULTILINESTRING ZM`. Z values are ignored.
+pub fn read_multi_line_string_m(wkt: &str) -> Result<Vec<LineStringM>, WktError> {
+	let geometry = parse(wkt, "MULTILINESTRING")?;
+	geometry.parts.iter().map(|part| to_line_string_m(&geometry, part)).collect()
+}
+
+/// Writes a 2D `MULTILINESTRING`.
+pub fn write_multi_line_string(lines: &[LineStringMeasured]) -> String {
+	if lines.is_empty() {
+		return "MULTILINESTRING EMPTY".to_string();
+	}
+	let parts: Vec<String> = lines
+		.iter()
+		.map(|line| {
+			let points: Vec<Vector2> = line.into();
+			coordinates(points.iter().map(|point| vec![point.x, point.y]))
+		})
+		.collect();
+	format!("MULTILINESTRING ({})", parts.join(", "))
+}
+
+/// Writes a `MULTILINESTRING M`.
+pub fn write_multi_line_string_m(lines: &[LineStringM]) -> String {
+	if lines.is_empty() {
+		return "MULTILINESTRING M EMPTY".to_string();
+	}
+	let parts: Vec<String> = lines
+		.iter()
+		.map(|line| coordinates(line.into_tuples_measured().iter().map(|&(x, y, m)| vec![x, y, m])))
+		.collect();
+	format!("MULTILINESTRING M ({})", parts.join(", "))
+}
+
+fn write_measured(tuples: &[(f64, f64, f64)]) -> String {
+	format!("LINESTRING M {}", coordinates(tuples.iter().map(|&(x, y, m)| vec![x, y, m])))
+}
+
+/// Formats a coordinate list, for example `(1 2, 3 4)` or `EMPTY`.
+fn coordinates<I: Iterator<Item = Vec<f64>>>(points: I) -> String {
+	let points: Vec<String> = points
+		.map(|point| point.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" "))
+		.collect();
+	if points.is_empty() {
+		"EMPTY".to_string()
+	} else {
+		format!("({})", points.join(", "))
+	}
+}
+
+fn to_vector2(part: &[Vec<f64>]) -> Vec<Vector2> {
+	part.iter().map(|point| Vector2::new(point[0], point[1])).collect()
+}
+
+fn to_line_string_m(geometry: &Geometry, part: &[Vec<f64>]) -> Result<LineStringM, WktError> {
+	if !geometry.has_m {
+		return Err(WktError::MissingMeasures);
+	}
+	Ok(LineStringM::from_parts(to_vector2(part).into(), part.iter().map(|point| point[point.len() - 1]).collect()))
+}
+
+#[derive(Debug)]
+struct Geometry {
+	has_m: bool,
+	/// One list of points per linestring. Each point is `x y [z] [m]`.
+	parts: Vec<Vec<Vec<f64>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+	Word(String),
+	Number(f64),
+	Open,
+	Close,
+	Comma,
+}
+
+fn tokenise(wkt: &str) -> Result<Vec<Token>, WktError> {
+	let mut tokens = Vec::new();
+	let mut chars = wkt.char_indices().peekable();
+	while let Some(&(start, c)) = chars.peek() {
+		match c {
+			'(' => { tokens.push(Token::Open); chars.next(); }
+			')' => { tokens.push(Token::Close); chars.next(); }
+			',' => { tokens.push(Token::Comma); chars.next(); }
+			c if c.is_whitespace() => { chars.next(); }
+			c if c.is_ascii_alphabetic() => {
+				let mut end = start;
+				while let Some(&(index, c)) = chars.peek() {
+					if !c.is_ascii_alphabetic() {
+						break;
+					}
+					end = index + c.len_utf8();
+					chars.next();
+				}
+				tokens.push(Token::Word(wkt[start..end].to_ascii_uppercase()));
+			}
+			c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+				let mut end = start;
+				while let Some(&(index, c)) = chars.peek() {
+					let in_exponent = (c == '-' || c == '+') && matches!(wkt[..index].chars().last(), Some('e') | Some('E'));
+					if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || in_exponent || index == start) {
+						break;
+					}
+					end = index + c.len_utf8();
+					chars.next();
+				}
+				let text = &wkt[start..end];
+				tokens.push(Token::Number(text.parse().map_err(|_| WktError::UnexpectedToken(text.to_string()))?));
+			}
+			c => return Err(WktError::UnexpectedToken(c.to_string())),
+		}
+	}
+	Ok(tokens)
+}
+
+struct Parser {
+	tokens: Vec<Token>,
+	position: usize,
+}
+
+impl Parser {
+	fn next(&mut self) -> Result<Token, WktError> {
+		let token = self.tokens.get(self.position).cloned().ok_or(WktError::UnexpectedEnd)?;
+		self.position += 1;
+		Ok(token)
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.position)
+	}
+
+	fn expect(&mut self, expected: Token) -> Result<(), WktError> {
+		match self.next()? {
+			token if token == expected => Ok(()),
+			token => Err(unexpected(token)),
+		}
+	}
+
+	/// Reads `( x y ..., x y ... )` where each point has `dimension` numbers.
+	fn point_list(&mut self, dimension: usize) -> Result<Vec<Vec<f64>>, WktError> {
+		self.expect(Token::Open)?;
+		let mut points = Vec::new();
+		loop {
+			let mut point = Vec::with_capacity(dimension);
+			while let Some(&Token::Number(value)) = self.peek() {
+				point.push(value);
+				self.position += 1;
+			}
+			if point.len() != dimension {
+				return Err(WktError::WrongDimension { expected: dimension, found: point.len() });
+			}
+			points.push(point);
+			match self.next()? {
+				Token::Comma => continue,
+				Token::Close => return Ok(points),
+				token => return Err(unexpected(token)),
+			}
+		}
+	}
+
+	/// Counts the numbers in the first point, for geometries without a Z or M tag.
+	fn first_point_dimension(&self) -> usize {
+		self.tokens[self.position..]
+			.iter()
+			.skip_while(|token| **token == Token::Open)
+			.take_while(|token| matches!(token, Token::Number(_)))
+			.count()
+	}
+}
+
+fn unexpected(token: Token) -> WktError {
+	WktError::UnexpectedToken(match token {
+		Token::Word(word) => word,
+		Token::Number(number) => number.to_string(),
+		Token::Open => "(".to_string(),
+		Token::Close => ")".to_string(),
+		Token::Comma => ",".to_string(),
+	})
+}
+
+/// Parses a `LINESTRING` or `MULTILINESTRING` into parts. An empty `LINESTRING` gives one empty part.
+fn parse(wkt: &str, geometry_type: &str) -> Result<Geometry, WktError> {
+	let mut parser = Parser { tokens: tokenise(wkt)?, position: 0 };
+	match parser.next()? {
+		Token::Word(word) if word == geometry_type => {}
+		Token::Word(word) => return Err(WktError::UnsupportedGeometry(word)),
+		token => return Err(unexpected(token)),
+	}
+	let (mut has_z, mut has_m) = (false, false);
+	let mut tagged = false;
+	if let Some(Token::Word(word)) = parser.peek() {
+		match word.as_str() {
+			"Z" => { has_z = true; tagged = true; }
+			"M" => { has_m = true; tagged = true; }
+			"ZM" => { has_z = true; has_m = true; tagged = true; }
+			_ => {}
+		}
+		if tagged {
+			parser.position += 1;
+		}
+	}
+	let is_multi = geometry_type == "MULTILINESTRING";
+	let parts = if let Some(Token::Word(word)) = parser.peek() {
+		if word != "EMPTY" {
+			return Err(WktError::UnexpectedToken(word.clone()));
+		}
+		parser.position += 1;
+		if is_multi { Vec::new() } else { vec![Vec::new()] }
+	} else {
+		if !tagged {
+			// untagged 3D and 4D coordinates are Z and ZM, as in PostGIS
+			match parser.first_point_dimension() {
+				3 => has_z = true,
+				4 => { has_z = true; has_m = true; }
+				_ => {}
+			}
+		}
+		let dimension = 2 + has_z as usize + has_m as usize;
+		if is_multi {
+			let mut parts = Vec::new();
+			parser.expect(Token::Open)?;
+			loop {
+				parts.push(parser.point_list(dimension)?);
+				match parser.next()? {
+					Token::Comma => continue,
+					Token::Close => break,
+					token => return Err(unexpected(token)),
+				}
+			}
+			parts
+		} else {
+			vec![parser.point_list(dimension)?]
+		}
+	};
+	if let Some(token) = parser.peek() {
+		return Err(unexpected(token.clone()));
+	}
+	Ok(Geometry { has_m, parts })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn read_and_write_line_string() {
+		let ls = LineStringMeasured::from_wkt("LINESTRING (0 0, 3 0, 3 4)").unwrap();
+		assert_eq!(ls.into_tuples(), vec![(0.0, 0.0), (3.0, 0.0), (3.0, 4.0)]);
+		assert_eq!(ls.to_wkt(), "LINESTRING (0 0, 3 0, 3 4)");
+		assert_eq!(ls.to_wkt_measured(0.0, 70.0), "LINESTRING M (0 0 0, 3 0 30, 3 4 70)");
+		assert_eq!(LineStringMeasured::from_wkt("linestring z(1.5 -2 9,4e1 2E-1 9)").unwrap().into_tuples(), vec![(1.5, -2.0), (40.0, 0.2)]);
+		assert_eq!(LineStringMeasured::from_wkt("LINESTRING EMPTY").unwrap().to_wkt(), "LINESTRING EMPTY");
+	}
+
+	#[test]
+	fn read_and_write_measured() {
+		let wkt = "LINESTRING M (0 0 10, 3 0 12.5, 3 4 20)";
+		let ls = LineStringM::from_wkt(wkt).unwrap();
+		assert_eq!(ls.measures, vec![10.0, 12.5, 20.0]);
+		assert_eq!(ls.to_wkt(), wkt);
+		let zm = LineStringM::from_wkt("LINESTRING ZM (0 0 5 10, 3 0 5 12.5)").unwrap();
+		assert_eq!(zm.into_tuples_measured(), vec![(0.0, 0.0, 10.0), (3.0, 0.0, 12.5)]);
+		assert_eq!(LineStringM::from_wkt("LINESTRING Z (0 0 10, 3 0 12.5)"), Err(WktError::MissingMeasures));
+		assert_eq!(LineStringM::from_wkt("LINESTRING (0 0 5 10, 3 0 5 12.5)").unwrap().measures, vec![10.0, 12.5]);
+	}
+
+	#[test]
+	fn read_and_write_multi_line_string() {
+		let wkt = "MULTILINESTRING ((0 0, 1 0), (5 5, 6 6, 7 5))";
+		let lines = read_multi_line_string(wkt).unwrap();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[1].into_tuples(), vec![(5.0, 5.0), (6.0, 6.0), (7.0, 5.0)]);
+		assert_eq!(write_multi_line_string(&lines), wkt);
+
+		let wkt = "MULTILINESTRING M ((0 0 1, 1 0 2), (5 5 3, 6 6 4))";
+		assert_eq!(write_multi_line_string_m(&read_multi_line_string_m(wkt).unwrap()), wkt);
+		assert_eq!(read_multi_line_string("MULTILINESTRING EMPTY").unwrap(), vec![]);
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(LineStringMeasured::from_wkt("POINT (1 2)"), Err(WktError::UnsupportedGeometry("POINT".to_string())));
+		assert_eq!(LineStringMeasured::from_wkt("LINESTRING (0 0, 1)"), Err(WktError::WrongDimension { expected: 2, found: 1 }));
+		assert_eq!(LineStringMeasured::from_wkt("LINESTRING M (0 0, 1 1)"), Err(WktError::WrongDimension { expected: 3, found: 2 }));
+		assert_eq!(LineStringMeasured::from_wkt("LINESTRING (0 0, 1 1"), Err(WktError::UnexpectedEnd));
+		assert_eq!(LineStringMeasured::from_wkt("LINESTRING (0 0, 1 1) x"), Err(WktError::UnexpectedToken("X".to_string())));
+		assert_eq!(read_multi_line_string("LINESTRING (0 0, 1 1)"), Err(WktError::UnsupportedGeometry("LINESTRING".to_string())));
+	}
+}